@@ -2,9 +2,21 @@
 //!
 //! Provides native bindings to DuckDB through Rustler.
 
-use duckdb::{Connection as DuckDBConnection, arrow::array::Array, types::ValueRef};
+use arrow_ipc::writer::StreamWriter;
+use duckdb::{
+    Connection as DuckDBConnection,
+    arrow::{array::Array, record_batch::RecordBatch},
+    types::{ToSql, Value, ValueRef},
+};
 use rustler::{Encoder, Env, NifResult, ResourceArc, Term};
-use std::sync::Mutex;
+use sha2::{Digest, Sha256};
+use std::borrow::Cow;
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::ops::Deref;
+use std::sync::mpsc::{self, Receiver, Sender, SyncSender};
+use std::sync::{Arc, Condvar, Mutex, MutexGuard, Once, OnceLock, PoisonError};
+use std::thread;
+use std::time::{Duration, Instant};
 
 mod atoms {
     rustler::atoms! {
@@ -14,6 +26,10 @@ mod atoms {
         connection_failed,
         query_syntax_error,
         database_error,
+        constraint_violation,
+        type_mismatch,
+        out_of_memory,
+        timeout,
         nil,
         // Type atoms
         null,
@@ -27,52 +43,600 @@ mod atoms {
         text,
         blob,
         timestamp,
+        timestamptz,
         date,
         time,
         interval,
+        decimal,
+        utiny_int,
+        usmall_int,
+        uinteger,
+        ubig_int,
+        list,
+        array,
+        struct_ = "struct",
+        map,
+        enum_ = "enum",
+        union,
+        unknown,
+        null_key,
+        nan,
+        infinity,
+        negative_infinity,
+        // Cursor atoms
+        more,
+        eof,
+        // Describe atoms
+        result_set,
+        no_result,
+        // Row limit atoms
+        truncated,
+        complete,
+        // Scalar function atoms
+        ducky_scalar_call,
+        // Query log atoms
+        query_log,
     }
 }
 
 /// Error type that can be returned to Erlang.
+///
+/// DuckDB errors are classified by their message prefix:
+/// - `Parser Error` becomes `query_syntax_error`
+/// - `Constraint Error` becomes `constraint_violation`
+/// - `Conversion Error` and `Mismatch Type Error` become `type_mismatch`
+/// - `Out of Memory Error` becomes `out_of_memory`
+/// - Anything else becomes `database_error`
 #[derive(Debug)]
 pub enum DuckyError {
     ConnectionFailed(String),
     QuerySyntaxError(String),
+    ConstraintViolation(String),
+    TypeMismatch(String),
+    OutOfMemory(String),
     DatabaseError(String),
+    /// Query interrupted after running for the given number of milliseconds.
+    Timeout(u64),
+    /// Error raised while running a statement, with the statement's SQL.
+    Statement {
+        error: Box<DuckyError>,
+        sql: String,
+    },
 }
 
-impl Encoder for DuckyError {
-    fn encode<'a>(&self, env: Env<'a>) -> Term<'a> {
-        let reason = match self {
-            DuckyError::ConnectionFailed(msg) => {
-                (atoms::connection_failed(), msg.as_str()).encode(env)
+impl DuckyError {
+    /// Attaches the SQL of the statement that raised an error.
+    fn in_statement(self, sql: &str) -> Self {
+        match self {
+            DuckyError::Timeout(_) | DuckyError::Statement { .. } => self,
+            error => DuckyError::Statement {
+                error: Box::new(error),
+                sql: sql.to_string(),
+            },
+        }
+    }
+
+    /// Names the parameter whose value couldn't be bound.
+    fn for_parameter(self, name: &str) -> Self {
+        let named = |msg: String| format!("Parameter {}: {}", name, msg);
+        match self {
+            DuckyError::TypeMismatch(msg) => DuckyError::TypeMismatch(named(msg)),
+            DuckyError::DatabaseError(msg) => DuckyError::DatabaseError(named(msg)),
+            error => error,
+        }
+    }
+
+    /// Whether this is DuckDB's error for a query stopped by an interrupt.
+    fn is_interrupt(&self) -> bool {
+        matches!(self.kind(), (_, Some(msg)) if msg.contains("INTERRUPT Error"))
+    }
+
+    /// Returns the atom naming the error and its message, if it has one.
+    fn kind(&self) -> (rustler::Atom, Option<&str>) {
+        match self {
+            DuckyError::ConnectionFailed(msg) => (atoms::connection_failed(), Some(msg.as_str())),
+            DuckyError::QuerySyntaxError(msg) => (atoms::query_syntax_error(), Some(msg.as_str())),
+            DuckyError::ConstraintViolation(msg) => {
+                (atoms::constraint_violation(), Some(msg.as_str()))
             }
-            DuckyError::QuerySyntaxError(msg) => {
-                (atoms::query_syntax_error(), msg.as_str()).encode(env)
+            DuckyError::TypeMismatch(msg) => (atoms::type_mismatch(), Some(msg.as_str())),
+            DuckyError::OutOfMemory(msg) => (atoms::out_of_memory(), Some(msg.as_str())),
+            DuckyError::DatabaseError(msg) => (atoms::database_error(), Some(msg.as_str())),
+            DuckyError::Timeout(_) => (atoms::timeout(), None),
+            DuckyError::Statement { error, .. } => error.kind(),
+        }
+    }
+
+    /// Encodes the reason without the `error` tag: `{atom, message}`,
+    /// `{atom, message, sql}` for errors raised by a statement, or
+    /// `{timeout, milliseconds}`.
+    fn reason<'a>(&self, env: Env<'a>) -> Term<'a> {
+        match (self.kind(), self) {
+            ((kind, _), DuckyError::Timeout(ms)) => (kind, *ms).encode(env),
+            ((kind, None), _) => kind.encode(env),
+            ((kind, Some(msg)), DuckyError::Statement { sql, .. }) => {
+                (kind, msg, sql.as_str()).encode(env)
             }
-            DuckyError::DatabaseError(msg) => (atoms::database_error(), msg.as_str()).encode(env),
-        };
-        (atoms::error(), reason).encode(env)
+            ((kind, Some(msg)), _) => (kind, msg).encode(env),
+        }
+    }
+}
+
+impl Encoder for DuckyError {
+    fn encode<'a>(&self, env: Env<'a>) -> Term<'a> {
+        (atoms::error(), self.reason(env)).encode(env)
     }
 }
 
 impl From<duckdb::Error> for DuckyError {
     fn from(err: duckdb::Error) -> Self {
-        DuckyError::DatabaseError(err.to_string())
+        use duckdb::Error;
+
+        let msg = err.to_string();
+        match err {
+            // Raised by duckdb-rs itself when reading a value as the wrong type
+            Error::FromSqlConversionFailure(..)
+            | Error::IntegralValueOutOfRange(..)
+            | Error::InvalidColumnType(..) => DuckyError::TypeMismatch(msg),
+            _ if msg.contains("Parser Error") => DuckyError::QuerySyntaxError(msg),
+            _ if msg.contains("Constraint Error") => DuckyError::ConstraintViolation(msg),
+            _ if msg.contains("Conversion Error") || msg.contains("Mismatch Type Error") => {
+                DuckyError::TypeMismatch(msg)
+            }
+            _ if msg.contains("Out of Memory Error") => DuckyError::OutOfMemory(msg),
+            _ => DuckyError::DatabaseError(msg),
+        }
     }
 }
 
 /// Resource wrapper for DuckDB connection with thread-safe access.
+///
+/// The connection is `None` once it has been closed with `close`. The
+/// interrupt handle lives outside the mutex so a running query can be
+/// cancelled while its NIF holds the lock.
 pub struct ConnectionResource {
-    #[allow(dead_code)]
-    connection: Mutex<DuckDBConnection>,
+    connection: Mutex<Option<DuckDBConnection>>,
+    interrupt: Arc<duckdb::InterruptHandle>,
+    /// Process told about every `execute_query`, set by `enable_query_log`.
+    query_log: Mutex<Option<rustler::LocalPid>>,
 }
 
 impl ConnectionResource {
     fn new(connection: DuckDBConnection) -> Self {
         Self {
-            connection: Mutex::new(connection),
+            interrupt: connection.interrupt_handle(),
+            connection: Mutex::new(Some(connection)),
+            query_log: Mutex::new(None),
+        }
+    }
+
+    /// Locks the connection, failing if it has already been closed.
+    fn lock(&self) -> Result<ConnectionGuard<'_>, DuckyError> {
+        let guard = self.lock_slot();
+
+        if guard.is_none() {
+            return Err(DuckyError::DatabaseError("Connection closed".to_string()));
+        }
+        Ok(ConnectionGuard(guard))
+    }
+
+    /// Locks the slot holding the connection, recovering it if poisoned.
+    ///
+    /// Panics in NIFs come from converting values between terms and DuckDB
+    /// types. DuckDB has either finished with the statement or not started
+    /// it by then, so the connection is still consistent and refusing to
+    /// hand it out would only make it unusable until garbage collected.
+    fn lock_slot(&self) -> MutexGuard<'_, Option<DuckDBConnection>> {
+        self.connection
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+    }
+
+    /// Sets or clears the process told about every `execute_query`.
+    fn set_query_log(&self, pid: Option<rustler::LocalPid>) {
+        *self.query_log_slot() = pid;
+    }
+
+    /// Sends `{query_log, sql, duration_micros}` to the query log process,
+    /// if one is set.
+    fn log_query(&self, env: Env, sql: &str, duration: Duration) {
+        let pid = *self.query_log_slot();
+        if let Some(pid) = pid {
+            let micros = duration.as_micros() as u64;
+            // A dead log process shouldn't fail the query
+            let _ = env.send(&pid, (atoms::query_log(), sql, micros));
+        }
+    }
+
+    fn query_log_slot(&self) -> MutexGuard<'_, Option<rustler::LocalPid>> {
+        self.query_log
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+#[rustler::resource_impl]
+impl rustler::Resource for ConnectionResource {
+    /// Interrupts the running query when the process that started it dies.
+    fn down<'a>(&'a self, _env: Env<'a>, _pid: rustler::LocalPid, _mon: rustler::Monitor) {
+        self.interrupt.interrupt();
+    }
+}
+
+/// Monitor on the process running a query, so the query is interrupted
+/// if that process dies. The monitor is removed when dropped, which must
+/// happen before the connection lock is released.
+struct CallerMonitor<'a> {
+    env: Env<'a>,
+    conn: &'a ResourceArc<ConnectionResource>,
+    monitor: Option<rustler::Monitor>,
+}
+
+impl<'a> CallerMonitor<'a> {
+    fn new(env: Env<'a>, conn: &'a ResourceArc<ConnectionResource>) -> Self {
+        let monitor = conn.monitor(Some(env), &env.pid());
+        Self { env, conn, monitor }
+    }
+}
+
+impl Drop for CallerMonitor<'_> {
+    fn drop(&mut self) {
+        if let Some(monitor) = &self.monitor {
+            self.conn.demonitor(Some(self.env), monitor);
+        }
+    }
+}
+
+/// Thread interrupting connections whose query has run past its deadline.
+///
+/// One thread serves every `execute_query_timeout` call, sleeping until the
+/// earliest pending deadline. Deadlines are removed under the same lock the
+/// thread fires them under, so once `cancel` returns a deadline can't
+/// interrupt a later query on the same connection.
+#[derive(Default)]
+struct InterruptTimer {
+    state: Mutex<InterruptTimerState>,
+    changed: Condvar,
+}
+
+#[derive(Default)]
+struct InterruptTimerState {
+    next_id: u64,
+    pending: BTreeMap<(Instant, u64), Arc<duckdb::InterruptHandle>>,
+    fired: HashSet<u64>,
+}
+
+impl InterruptTimer {
+    /// Returns the shared timer, starting its thread on first use.
+    fn shared() -> &'static InterruptTimer {
+        static TIMER: OnceLock<InterruptTimer> = OnceLock::new();
+        static STARTED: Once = Once::new();
+
+        let timer = TIMER.get_or_init(InterruptTimer::default);
+        STARTED.call_once(|| {
+            thread::spawn(move || timer.run());
+        });
+        timer
+    }
+
+    fn lock_state(&self) -> MutexGuard<'_, InterruptTimerState> {
+        self.state.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    fn run(&self) {
+        let mut state = self.lock_state();
+        loop {
+            let now = Instant::now();
+            while let Some(entry) = state.pending.first_entry() {
+                if entry.key().0 > now {
+                    break;
+                }
+                let ((_, id), interrupt) = entry.remove_entry();
+                interrupt.interrupt();
+                state.fired.insert(id);
+            }
+
+            state = match state.pending.keys().next() {
+                Some(&(deadline, _)) => {
+                    let wait = deadline.saturating_duration_since(now);
+                    self.changed
+                        .wait_timeout(state, wait)
+                        .unwrap_or_else(PoisonError::into_inner)
+                        .0
+                }
+                None => self
+                    .changed
+                    .wait(state)
+                    .unwrap_or_else(PoisonError::into_inner),
+            };
+        }
+    }
+
+    /// Interrupts the connection behind `interrupt` once `timeout` has
+    /// passed, unless cancelled first. Returns `None` for timeouts too long
+    /// to ever expire.
+    fn schedule(&self, timeout: Duration, interrupt: Arc<duckdb::InterruptHandle>) -> Option<u64> {
+        let deadline = Instant::now().checked_add(timeout)?;
+        let mut state = self.lock_state();
+        let id = state.next_id;
+        state.next_id += 1;
+        state.pending.insert((deadline, id), interrupt);
+        self.changed.notify_one();
+        Some(id)
+    }
+
+    /// Removes a deadline, returning whether it had already fired.
+    fn cancel(&self, id: u64) -> bool {
+        let mut state = self.lock_state();
+        state.pending.retain(|&(_, pending), _| pending != id);
+        state.fired.remove(&id)
+    }
+}
+
+/// Lock on an open connection, dereferencing to the DuckDB connection.
+struct ConnectionGuard<'a>(MutexGuard<'a, Option<DuckDBConnection>>);
+
+impl Deref for ConnectionGuard<'_> {
+    type Target = DuckDBConnection;
+
+    fn deref(&self) -> &DuckDBConnection {
+        self.0
+            .as_ref()
+            .expect("connection presence is checked when locking")
+    }
+}
+
+/// Resource buffering rows for bulk insertion through DuckDB's appender.
+///
+/// Rows are converted when appended and written in one pass on flush, so
+/// the connection is only locked while flushing.
+pub struct AppenderResource {
+    conn: ResourceArc<ConnectionResource>,
+    table: String,
+    rows: Mutex<Vec<Vec<Value>>>,
+}
+
+impl AppenderResource {
+    /// Writes all buffered rows to the table.
+    fn flush(&self) -> Result<(), DuckyError> {
+        let rows =
+            std::mem::take(&mut *self.rows.lock().map_err(|e| {
+                DuckyError::DatabaseError(format!("Appender mutex poisoned: {}", e))
+            })?);
+        if rows.is_empty() {
+            return Ok(());
+        }
+
+        let connection = self.conn.lock()?;
+
+        let mut appender = connection.appender(&self.table)?;
+        for row in &rows {
+            appender.append_row(param_refs(row).as_slice())?;
+        }
+        appender.flush()?;
+        Ok(())
+    }
+}
+
+impl Drop for AppenderResource {
+    fn drop(&mut self) {
+        // Rows still buffered when the appender is garbage collected are
+        // written on a best-effort basis
+        let _ = self.flush();
+    }
+}
+
+/// Resource for a statement that is prepared once and executed many times.
+///
+/// `duckdb::Statement` borrows its connection, so the handle keeps the SQL
+/// text and relies on the connection's prepared statement cache to hold
+/// the prepared form. Re-executing takes the statement from the cache
+/// instead of parsing and planning it again; if it was evicted it is
+/// transparently prepared again.
+pub struct PreparedStatementResource {
+    conn: ResourceArc<ConnectionResource>,
+    sql: String,
+}
+
+/// Resource streaming a query result to Erlang in batches.
+///
+/// A worker thread runs the query on a connection of its own and feeds
+/// record batches through a bounded channel, so only a small number of
+/// batches is buffered at a time. DuckDB ends a streaming result when its
+/// connection runs another statement, so the cursor can't share the
+/// connection it was opened on without locking it for its whole life.
+pub struct CursorResource {
+    state: Mutex<CursorState>,
+}
+
+struct CursorState {
+    receiver: Option<Receiver<RecordBatch>>,
+    batch: Option<RecordBatch>,
+    /// Index in the whole result of the current batch's first row
+    batch_start: usize,
+    offset: usize,
+}
+
+impl CursorState {
+    /// Moves on to the next record batch, returning `false` at the end of
+    /// the result.
+    fn next_batch(&mut self) -> bool {
+        let next = self.receiver.as_ref().and_then(|r| r.recv().ok());
+        match next {
+            Some(batch) => {
+                if let Some(previous) = self.batch.replace(batch) {
+                    self.batch_start += previous.num_rows();
+                }
+                self.offset = 0;
+                true
+            }
+            None => {
+                self.finish();
+                false
+            }
+        }
+    }
+
+    /// Releases the worker thread and with it the cursor's connection.
+    fn finish(&mut self) {
+        self.receiver = None;
+        self.batch = None;
+        self.offset = 0;
+    }
+}
+
+/// Resource holding several connections to one database, so independent
+/// queries can run in parallel on the dirty schedulers.
+///
+/// Idle connections sit in a free list. A query checks one out and blocks
+/// until another query returns one if all of them are busy.
+pub struct PoolResource {
+    idle: Mutex<Vec<DuckDBConnection>>,
+    available: Condvar,
+}
+
+impl PoolResource {
+    /// Takes an idle connection, waiting for one to be returned if needed.
+    fn checkout(&self) -> Result<PooledConnection<'_>, DuckyError> {
+        let lock_error =
+            |e: String| DuckyError::DatabaseError(format!("Failed to lock pool: {}", e));
+
+        let idle = self.idle.lock().map_err(|e| lock_error(e.to_string()))?;
+        let mut idle = self
+            .available
+            .wait_while(idle, |idle| idle.is_empty())
+            .map_err(|e| lock_error(e.to_string()))?;
+
+        Ok(PooledConnection {
+            pool: self,
+            connection: idle.pop(),
+        })
+    }
+}
+
+/// Connection checked out of a pool, returned to the free list on drop.
+struct PooledConnection<'a> {
+    pool: &'a PoolResource,
+    connection: Option<DuckDBConnection>,
+}
+
+impl Deref for PooledConnection<'_> {
+    type Target = DuckDBConnection;
+
+    fn deref(&self) -> &DuckDBConnection {
+        self.connection
+            .as_ref()
+            .expect("pooled connection is present until dropped")
+    }
+}
+
+impl Drop for PooledConnection<'_> {
+    fn drop(&mut self) {
+        if let (Some(connection), Ok(mut idle)) = (self.connection.take(), self.pool.idle.lock()) {
+            idle.push(connection);
+            self.pool.available.notify_one();
+        }
+    }
+}
+
+/// Resource for an open database that connections can be made to.
+///
+/// duckdb-rs doesn't expose the database handle itself, so this holds one
+/// connection that new connections are cloned from. The database stays
+/// open while this resource or any connection made from it is alive.
+pub struct DatabaseResource {
+    connection: Mutex<DuckDBConnection>,
+}
+
+/// Resource for one pending call of a scalar function registered with
+/// `register_scalar_function`, answered with `scalar_function_reply`.
+///
+/// If the resource is garbage collected without a reply, e.g. because the
+/// handler process died, the reply channel closes and the query fails
+/// instead of waiting forever.
+pub struct ScalarCallResource {
+    reply: Mutex<Option<SyncSender<ScalarReply>>>,
+}
+
+/// Result of one scalar function call: a string, NULL, or an error message.
+type ScalarReply = Result<Option<String>, String>;
+
+/// Arguments of one scalar function call, waiting to be sent to the
+/// handler process.
+struct ScalarCall {
+    args: Vec<Option<String>>,
+    reply: SyncSender<ScalarReply>,
+}
+
+/// Scalar function whose rows are computed by an Erlang process.
+struct ErlangScalar;
+
+/// Per-function state: the channel to the thread that messages the
+/// handler process.
+///
+/// `enif_send` can't be used from scheduler threads, and DuckDB may run
+/// the function on the dirty scheduler thread executing the query, so a
+/// dedicated thread sends the messages.
+#[derive(Clone, Default)]
+struct ErlangScalarState {
+    calls: Option<Sender<ScalarCall>>,
+}
+
+impl duckdb::vscalar::VScalar for ErlangScalar {
+    type State = ErlangScalarState;
+
+    unsafe fn invoke(
+        state: &Self::State,
+        input: &mut duckdb::core::DataChunkHandle,
+        output: &mut dyn duckdb::vtab::arrow::WritableVector,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        use duckdb::core::Inserter;
+        use duckdb::ffi::duckdb_string_t;
+        use duckdb::types::DuckString;
+
+        let calls = state
+            .calls
+            .as_ref()
+            .ok_or("Scalar function has no handler")?;
+        let rows = input.len();
+        let columns: Vec<_> = (0..input.num_columns())
+            .map(|i| input.flat_vector(i))
+            .collect();
+        let mut results = output.flat_vector();
+
+        for row in 0..rows {
+            let args = columns
+                .iter()
+                .map(|vector| {
+                    if vector.row_is_null(row as u64) {
+                        return None;
+                    }
+                    let mut value = vector.as_slice_with_len::<duckdb_string_t>(rows)[row];
+                    Some(DuckString::new(&mut value).as_str().into_owned())
+                })
+                .collect();
+
+            let (reply, response) = mpsc::sync_channel(1);
+            calls
+                .send(ScalarCall { args, reply })
+                .map_err(|_| "Scalar function handler has stopped")?;
+            match response.recv() {
+                Ok(Ok(Some(value))) => results.insert(row, value.as_str()),
+                Ok(Ok(None)) => results.set_null(row),
+                Ok(Err(msg)) => return Err(msg.into()),
+                Err(_) => return Err("Scalar function handler didn't reply".into()),
+            }
         }
+        Ok(())
+    }
+
+    fn signatures() -> Vec<duckdb::vscalar::ScalarFunctionSignature> {
+        use duckdb::core::{LogicalTypeHandle, LogicalTypeId};
+
+        vec![duckdb::vscalar::ScalarFunctionSignature::variadic(
+            LogicalTypeHandle::from(LogicalTypeId::Varchar),
+            LogicalTypeHandle::from(LogicalTypeId::Varchar),
+        )]
     }
 }
 
@@ -86,110 +650,4457 @@ impl ConnectionResource {
 /// - `Err(DuckyError)` on failure
 #[rustler::nif]
 fn connect(path: String) -> Result<ResourceArc<ConnectionResource>, DuckyError> {
-    let connection = if path == ":memory:" {
-        DuckDBConnection::open_in_memory()
-    } else {
-        DuckDBConnection::open(&path)
-    }
-    .map_err(|e| DuckyError::ConnectionFailed(e.to_string()))?;
+    let connection = open_connection(&path)?;
 
     Ok(ResourceArc::new(ConnectionResource::new(connection)))
 }
 
-/// Closes a database connection.
+/// Opens a database without connecting to it.
+///
+/// Unlike `connect`, which opens a separate in-memory database on every
+/// call, connections made with `database_connect` all share this
+/// database, so they see each other's tables even for `:memory:`.
 ///
 /// ## Arguments
-/// - `conn`: Connection resource to close
+/// - `path`: Database file path or `:memory:` for in-memory database
 ///
 /// ## Returns
-/// - `Ok(())` on success
-/// - `Err(DuckyError)` if close fails
+/// - `Ok(ResourceArc<DatabaseResource>)` on success
+/// - `Err(ConnectionFailed)` if the database can't be opened
 #[rustler::nif]
-fn close(conn: ResourceArc<ConnectionResource>) -> Result<rustler::Atom, DuckyError> {
-    // Attempt to lock the connection
-    let _guard = conn
-        .connection
-        .lock()
-        .map_err(|e| DuckyError::DatabaseError(format!("Connection mutex poisoned: {}", e)))?;
+fn open_database(path: String) -> Result<ResourceArc<DatabaseResource>, DuckyError> {
+    let connection = open_connection(&path)?;
 
-    // DuckDB connections are closed via Drop when ResourceArc is dropped
-    // We just validate the connection is accessible before allowing the drop
-    drop(_guard);
-    Ok(atoms::nil())
+    Ok(ResourceArc::new(DatabaseResource {
+        connection: Mutex::new(connection),
+    }))
 }
 
-/// Executes a SQL query with optional parameter binding.
-///
-/// Runs on a dirty CPU scheduler to avoid blocking the BEAM VM.
+/// Opens a new connection to a database opened with `open_database`.
 ///
-/// Handles both result-returning queries (SELECT, SHOW, etc.) and
-/// non-result statements (CREATE, INSERT, UPDATE, DELETE, etc.).
+/// Each connection has its own transactions and can run queries
+/// concurrently with the others.
 ///
 /// ## Arguments
-/// - `env`: NIF environment for term creation
-/// - `conn`: Connection resource
-/// - `sql`: SQL query string with optional `?` placeholders
-/// - `params_list`: Parameter values to bind (empty for non-parameterized queries)
+/// - `db`: Database resource
 ///
 /// ## Returns
-/// - `Ok({columns, rows})` where columns is a list of column names
-///   and rows is a list of rows (each row is a list of values)
-/// - For DDL/DML statements, returns empty columns and rows
-/// - `Err(DuckyError)` on failure
-#[rustler::nif(schedule = "DirtyCpu")]
-fn execute_query<'a>(
-    env: Env<'a>,
-    conn: ResourceArc<ConnectionResource>,
-    sql: String,
-    params_list: Vec<Term<'a>>,
-) -> Result<(Vec<String>, Vec<Vec<Term<'a>>>), DuckyError> {
-    use duckdb::types::ToSql;
-
-    let connection = conn
+/// - `Ok(ResourceArc<ConnectionResource>)` on success
+/// - `Err(ConnectionFailed)` if the connection fails
+#[rustler::nif]
+fn database_connect(
+    db: ResourceArc<DatabaseResource>,
+) -> Result<ResourceArc<ConnectionResource>, DuckyError> {
+    let connection = db
         .connection
         .lock()
-        .map_err(|e| DuckyError::DatabaseError(format!("Failed to lock connection: {}", e)))?;
+        .map_err(|e| DuckyError::DatabaseError(format!("Database mutex poisoned: {}", e)))?
+        .try_clone()
+        .map_err(|e| DuckyError::ConnectionFailed(e.to_string()))?;
 
-    // Convert Erlang terms to DuckDB params
-    let mut params: Vec<Box<dyn ToSql>> = Vec::new();
-    for term in params_list {
-        let param = term_to_duckdb_param(term)?;
-        params.push(param);
+    Ok(ResourceArc::new(ConnectionResource::new(connection)))
+}
+
+/// Opens a connection and loads a list of extensions into it.
+///
+/// Runs on a dirty IO scheduler since extensions may be downloaded.
+///
+/// ## Arguments
+/// - `path`: Database file path or `:memory:` for in-memory database
+/// - `extensions`: Names of extensions to install and load, in order
+///
+/// ## Returns
+/// - `Ok(ResourceArc<ConnectionResource>)` on success
+/// - `Err(DuckyError)` if the connection or any extension fails
+#[rustler::nif(schedule = "DirtyIo")]
+fn connect_with_extensions(
+    path: String,
+    extensions: Vec<String>,
+) -> Result<ResourceArc<ConnectionResource>, DuckyError> {
+    let connection = open_connection(&path)?;
+    for name in &extensions {
+        install_and_load(&connection, name)?;
     }
 
-    // Create references for binding
-    let param_refs: Vec<&dyn ToSql> = params.iter().map(|p| p.as_ref()).collect();
+    Ok(ResourceArc::new(ConnectionResource::new(connection)))
+}
+
+/// Opens a connection with DuckDB configuration options.
+///
+/// Supported options:
+/// - `"access_mode"`: `"automatic"`, `"read_only"` or `"read_write"`
+/// - `"max_memory"`: memory limit such as `"2GB"`
+/// - `"threads"`: number of worker threads
+/// - `"temp_directory"`: directory for spilling to disk
+///
+/// ## Arguments
+/// - `path`: Database file path or `:memory:` for in-memory database
+/// - `config_map`: Map of option name to value
+///
+/// ## Returns
+/// - `Ok(ResourceArc<ConnectionResource>)` on success
+/// - `Err(ConnectionFailed)` naming the offending key for unknown options
+///   or invalid values
+#[rustler::nif]
+fn connect_with_config(
+    path: String,
+    config_map: HashMap<String, Term>,
+) -> Result<ResourceArc<ConnectionResource>, DuckyError> {
+    let config = build_config(&config_map)?;
+
+    let connection = if path == ":memory:" {
+        DuckDBConnection::open_in_memory_with_flags(config)
+    } else {
+        DuckDBConnection::open_with_flags(&path, config)
+    }
+    .map_err(|e| DuckyError::ConnectionFailed(e.to_string()))?;
 
-    execute_statement(env, &connection, &sql, param_refs.as_slice())
+    Ok(ResourceArc::new(ConnectionResource::new(connection)))
 }
 
-/// Converts Arrow TimeUnit to DuckDB TimeUnit.
-fn arrow_to_duckdb_time_unit(
-    arrow_unit: duckdb::arrow::datatypes::TimeUnit,
-) -> duckdb::types::TimeUnit {
-    use duckdb::arrow::datatypes::TimeUnit as ArrowUnit;
-    use duckdb::types::TimeUnit as DuckUnit;
-    match arrow_unit {
-        ArrowUnit::Second => DuckUnit::Second,
-        ArrowUnit::Millisecond => DuckUnit::Millisecond,
-        ArrowUnit::Microsecond => DuckUnit::Microsecond,
-        ArrowUnit::Nanosecond => DuckUnit::Nanosecond,
+/// Builds a DuckDB configuration from connection options.
+fn build_config(options: &HashMap<String, Term>) -> Result<duckdb::Config, DuckyError> {
+    use duckdb::AccessMode;
+
+    let invalid = |key: &str| DuckyError::ConnectionFailed(format!("Invalid value for {}", key));
+
+    // Sorted so errors are reported consistently
+    let mut keys: Vec<&String> = options.keys().collect();
+    keys.sort();
+
+    let mut config = duckdb::Config::default();
+    for key in keys {
+        let value = options[key];
+        config = match key.as_str() {
+            "access_mode" => {
+                let mode = match value.decode::<String>().map_err(|_| invalid(key))?.as_str() {
+                    "automatic" => AccessMode::Automatic,
+                    "read_only" => AccessMode::ReadOnly,
+                    "read_write" => AccessMode::ReadWrite,
+                    _ => return Err(invalid(key)),
+                };
+                config.access_mode(mode)
+            }
+            "max_memory" => {
+                let limit = value.decode::<String>().map_err(|_| invalid(key))?;
+                config.max_memory(&limit)
+            }
+            "threads" => {
+                let threads = value.decode::<i64>().map_err(|_| invalid(key))?;
+                config.threads(threads)
+            }
+            "temp_directory" => {
+                let dir = value.decode::<String>().map_err(|_| invalid(key))?;
+                config.with("temp_directory", &dir)
+            }
+            _ => {
+                return Err(DuckyError::ConnectionFailed(format!(
+                    "Unknown config option: {}",
+                    key
+                )));
+            }
+        }
+        .map_err(|e| DuckyError::ConnectionFailed(format!("{}: {}", key, e)))?;
     }
+
+    Ok(config)
 }
 
-/// Normalizes a temporal value to microseconds based on TimeUnit.
-fn normalize_to_micros(time_unit: duckdb::types::TimeUnit, value: i64) -> i64 {
-    use duckdb::types::TimeUnit;
-    match time_unit {
-        TimeUnit::Second => value * 1_000_000,
-        TimeUnit::Millisecond => value * 1_000,
-        TimeUnit::Microsecond => value,
-        TimeUnit::Nanosecond => value / 1_000,
+/// Opens a DuckDB connection for a file path or `:memory:`.
+fn open_connection(path: &str) -> Result<DuckDBConnection, DuckyError> {
+    if path == ":memory:" {
+        DuckDBConnection::open_in_memory()
+    } else {
+        DuckDBConnection::open(path)
     }
+    .map_err(|e| DuckyError::ConnectionFailed(e.to_string()))
 }
 
-/// Converts a DuckDB ValueRef to an Erlang term.
-fn value_to_term<'a, 'b>(env: Env<'a>, value: ValueRef<'b>) -> NifResult<Term<'a>> {
+/// Installs and loads a DuckDB extension such as `json` or `parquet`.
+///
+/// Runs on a dirty IO scheduler since installing may download the
+/// extension. Extensions like `httpfs` that aren't bundled need network
+/// access and fail in sandboxed environments.
+///
+/// ## Arguments
+/// - `conn`: Connection resource
+/// - `name`: Extension name
+///
+/// ## Returns
+/// - `Ok(nil)` on success
+/// - `Err(QuerySyntaxError)` if the name isn't a plain identifier
+/// - `Err(DatabaseError)` with DuckDB's message if install or load fails
+#[rustler::nif(schedule = "DirtyIo")]
+fn load_extension(
+    conn: ResourceArc<ConnectionResource>,
+    name: String,
+) -> Result<rustler::Atom, DuckyError> {
+    let connection = conn.lock()?;
+
+    install_and_load(&connection, &name)?;
+    Ok(atoms::nil())
+}
+
+/// Loads `httpfs` and sets the credentials used to read `s3://` paths.
+///
+/// Runs on a dirty IO scheduler since `httpfs` may be downloaded. The
+/// secret is removed from any error message so it can't end up in logs.
+///
+/// ## Arguments
+/// - `conn`: Connection resource
+/// - `key_id`: Access key ID
+/// - `secret`: Secret access key
+/// - `region`: Region such as `us-east-1`
+/// - `endpoint`: Custom endpoint for S3-compatible storage, or `nil`
+///
+/// ## Returns
+/// - `Ok(nil)` on success
+/// - `Err(DatabaseError)` if `httpfs` can't be loaded or a setting fails
+#[rustler::nif(schedule = "DirtyIo")]
+fn set_s3_credentials(
+    conn: ResourceArc<ConnectionResource>,
+    key_id: String,
+    secret: String,
+    region: String,
+    endpoint: Option<String>,
+) -> Result<rustler::Atom, DuckyError> {
+    let connection = conn.lock()?;
+
+    install_and_load(&connection, "httpfs")?;
+
+    let mut settings = vec![
+        ("s3_access_key_id", key_id),
+        ("s3_secret_access_key", secret.clone()),
+        ("s3_region", region),
+    ];
+    if let Some(endpoint) = endpoint {
+        settings.push(("s3_endpoint", endpoint));
+    }
+
+    for (name, value) in settings {
+        let sql = format!("SET {} = {}", name, quote_literal(&value));
+        connection.execute(&sql, []).map_err(|e| {
+            let msg = e.to_string();
+            let msg = if secret.is_empty() {
+                msg
+            } else {
+                msg.replace(&secret, "[REDACTED]")
+            };
+            DuckyError::DatabaseError(format!("Failed to set {}: {}", name, msg))
+        })?;
+    }
+    Ok(atoms::nil())
+}
+
+/// Runs `INSTALL` followed by `LOAD` for an extension.
+fn install_and_load(connection: &DuckDBConnection, name: &str) -> Result<(), DuckyError> {
+    if !is_plain_identifier(name) {
+        return Err(DuckyError::QuerySyntaxError(format!(
+            "Invalid extension name: {}",
+            name
+        )));
+    }
+
+    connection.execute_batch(&format!("INSTALL {name}; LOAD {name};"))?;
+    Ok(())
+}
+
+/// Attaches another database file to the connection under an alias.
+///
+/// Tables in the attached database are queried as `alias.table`. Attached
+/// databases share the connection's transaction context, so statements
+/// touching several databases between `begin_transaction` and `commit`
+/// are committed or rolled back together.
+///
+/// ## Arguments
+/// - `conn`: Connection resource
+/// - `path`: Path to the database file, created if it doesn't exist
+/// - `alias`: Name to attach the database as
+/// - `options`: Attach options
+///   - `"read_only"`: Attach the database read-only when `true`
+///
+/// ## Returns
+/// - `Ok(nil)` on success
+/// - `Err(QuerySyntaxError)` if the alias or an option is invalid
+/// - `Err(DatabaseError)` if the alias is already attached or the file
+///   can't be opened
+#[rustler::nif(schedule = "DirtyIo")]
+fn attach(
+    conn: ResourceArc<ConnectionResource>,
+    path: String,
+    alias: String,
+    options: HashMap<String, Term>,
+) -> Result<rustler::Atom, DuckyError> {
+    if !is_plain_identifier(&alias) {
+        return Err(DuckyError::QuerySyntaxError(format!(
+            "Invalid database alias: {}",
+            alias
+        )));
+    }
+
+    let mut sql = format!("ATTACH {} AS {}", quote_literal(&path), alias);
+    for (key, value) in sorted_options(&options) {
+        match key.as_str() {
+            "read_only" => {
+                let read_only = value.decode::<bool>().map_err(|_| {
+                    DuckyError::QuerySyntaxError("Invalid value for read_only".to_string())
+                })?;
+                if read_only {
+                    sql.push_str(" (READ_ONLY)");
+                }
+            }
+            _ => {
+                return Err(DuckyError::QuerySyntaxError(format!(
+                    "Unknown attach option: {}",
+                    key
+                )));
+            }
+        }
+    }
+
+    let connection = conn.lock()?;
+    connection.execute(&sql, [])?;
+    Ok(atoms::nil())
+}
+
+/// Detaches a database previously attached with `attach`.
+///
+/// Runs on a dirty IO scheduler since it waits for any statement running on
+/// the connection.
+///
+/// ## Arguments
+/// - `conn`: Connection resource
+/// - `alias`: Alias the database was attached as
+///
+/// ## Returns
+/// - `Ok(nil)` on success
+/// - `Err(QuerySyntaxError)` if the alias isn't a plain identifier
+/// - `Err(DatabaseError)` if no database is attached under the alias
+#[rustler::nif(schedule = "DirtyIo")]
+fn detach(
+    conn: ResourceArc<ConnectionResource>,
+    alias: String,
+) -> Result<rustler::Atom, DuckyError> {
+    if !is_plain_identifier(&alias) {
+        return Err(DuckyError::QuerySyntaxError(format!(
+            "Invalid database alias: {}",
+            alias
+        )));
+    }
+
+    let connection = conn.lock()?;
+    connection.execute(&format!("DETACH {}", alias), [])?;
+    Ok(atoms::nil())
+}
+
+/// Creates a schema unless it already exists.
+///
+/// Runs on a dirty IO scheduler since it waits for any statement running on
+/// the connection.
+///
+/// ## Arguments
+/// - `conn`: Connection resource
+/// - `name`: Schema name
+///
+/// ## Returns
+/// - `Ok(nil)` on success
+/// - `Err(QuerySyntaxError)` if the name isn't a plain identifier
+/// - `Err(DatabaseError)` if the schema can't be created
+#[rustler::nif(schedule = "DirtyIo")]
+fn create_schema(
+    conn: ResourceArc<ConnectionResource>,
+    name: String,
+) -> Result<rustler::Atom, DuckyError> {
+    validate_schema_name(&name)?;
+
+    let connection = conn.lock()?;
+    connection.execute(&format!("CREATE SCHEMA IF NOT EXISTS {}", name), [])?;
+    Ok(atoms::nil())
+}
+
+/// Sets the schema that unqualified table names resolve to.
+///
+/// Runs on a dirty IO scheduler since it waits for any statement running on
+/// the connection.
+///
+/// ## Arguments
+/// - `conn`: Connection resource
+/// - `name`: Schema name
+///
+/// ## Returns
+/// - `Ok(nil)` on success
+/// - `Err(QuerySyntaxError)` if the name isn't a plain identifier
+/// - `Err(DatabaseError)` if the schema doesn't exist
+#[rustler::nif(schedule = "DirtyIo")]
+fn set_schema(
+    conn: ResourceArc<ConnectionResource>,
+    name: String,
+) -> Result<rustler::Atom, DuckyError> {
+    validate_schema_name(&name)?;
+
+    let connection = conn.lock()?;
+    connection.execute(&format!("SET schema = {}", quote_literal(&name)), [])?;
+    Ok(atoms::nil())
+}
+
+/// Rejects schema names that couldn't be spliced into SQL safely.
+fn validate_schema_name(name: &str) -> Result<(), DuckyError> {
+    if is_plain_identifier(name) {
+        Ok(())
+    } else {
+        Err(DuckyError::QuerySyntaxError(format!(
+            "Invalid schema name: {}",
+            name
+        )))
+    }
+}
+
+/// Reads the current value of a DuckDB setting such as `threads`.
+///
+/// Runs on a dirty IO scheduler since it waits for any statement running on
+/// the connection.
+///
+/// ## Arguments
+/// - `env`: NIF environment for term creation
+/// - `conn`: Connection resource
+/// - `name`: Setting name
+///
+/// ## Returns
+/// - `Ok(value)` decoded like a query result value
+/// - `Err(QuerySyntaxError)` if the name isn't a plain identifier
+/// - `Err(DatabaseError)` if the setting doesn't exist
+#[rustler::nif(schedule = "DirtyIo")]
+fn get_setting<'a>(
+    env: Env<'a>,
+    conn: ResourceArc<ConnectionResource>,
+    name: String,
+) -> Result<Term<'a>, DuckyError> {
+    validate_setting_name(&name)?;
+    let connection = conn.lock()?;
+
+    let (_, rows) = execute_statement(
+        env,
+        &connection,
+        "SELECT current_setting(?)",
+        &[&name as &dyn ToSql],
+    )?;
+    rows.into_iter()
+        .next()
+        .and_then(|row| row.into_iter().next())
+        .ok_or_else(|| DuckyError::DatabaseError(format!("Unknown setting: {}", name)))
+}
+
+/// Changes a DuckDB setting for the connection's database.
+///
+/// Runs on a dirty IO scheduler since it waits for any statement running on
+/// the connection.
+///
+/// ## Arguments
+/// - `conn`: Connection resource
+/// - `name`: Setting name, e.g. `threads` or `memory_limit`
+/// - `value`: New value as a string, integer, float or boolean
+///
+/// ## Returns
+/// - `Ok(nil)` on success
+/// - `Err(QuerySyntaxError)` if the name isn't a plain identifier or the
+///   value has an unsupported type
+/// - `Err(DatabaseError)` if DuckDB rejects the setting or value
+#[rustler::nif(schedule = "DirtyIo")]
+fn set_setting(
+    conn: ResourceArc<ConnectionResource>,
+    name: String,
+    value: Term,
+) -> Result<rustler::Atom, DuckyError> {
+    validate_setting_name(&name)?;
+
+    // SET doesn't accept bound parameters, so the value is inlined
+    let literal = match term_to_duckdb_param(value)? {
+        Value::Text(text) => quote_literal(&text),
+        Value::Boolean(b) => b.to_string(),
+        Value::BigInt(i) => i.to_string(),
+        Value::Double(f) if f.is_finite() => f.to_string(),
+        _ => {
+            return Err(DuckyError::QuerySyntaxError(format!(
+                "Unsupported value for setting {}",
+                name
+            )));
+        }
+    };
+
+    let connection = conn.lock()?;
+    connection.execute(&format!("SET {} = {}", name, literal), [])?;
+    Ok(atoms::nil())
+}
+
+/// Sets how many worker threads DuckDB uses to run queries.
+///
+/// Runs on a dirty IO scheduler since it waits for any statement running on
+/// the connection.
+///
+/// The setting applies to the whole database, so it also affects other
+/// connections to it. Lowering it helps when many connections run queries
+/// at once and would otherwise oversubscribe the CPU.
+///
+/// ## Arguments
+/// - `conn`: Connection resource
+/// - `threads`: Number of threads, at least 1
+///
+/// ## Returns
+/// - `Ok(nil)` on success
+/// - `Err(QuerySyntaxError)` if the count isn't positive
+/// - `Err(DatabaseError)` if DuckDB rejects the value
+#[rustler::nif(schedule = "DirtyIo")]
+fn set_threads(
+    conn: ResourceArc<ConnectionResource>,
+    threads: i64,
+) -> Result<rustler::Atom, DuckyError> {
+    if threads < 1 {
+        return Err(DuckyError::QuerySyntaxError(format!(
+            "Invalid thread count: {}",
+            threads
+        )));
+    }
+
+    let connection = conn.lock()?;
+    connection.execute(&format!("SET threads TO {}", threads), [])?;
+    Ok(atoms::nil())
+}
+
+/// Reads how many worker threads DuckDB uses to run queries.
+///
+/// Runs on a dirty IO scheduler since it waits for any statement running on
+/// the connection.
+///
+/// ## Arguments
+/// - `conn`: Connection resource
+///
+/// ## Returns
+/// - `Ok(threads)` on success
+/// - `Err(DuckyError)` on failure
+#[rustler::nif(schedule = "DirtyIo")]
+fn get_threads(conn: ResourceArc<ConnectionResource>) -> Result<i64, DuckyError> {
+    let connection = conn.lock()?;
+    Ok(
+        connection.query_row("SELECT current_setting('threads')::BIGINT", [], |row| {
+            row.get(0)
+        })?,
+    )
+}
+
+/// Rejects setting names that aren't plain identifiers.
+fn validate_setting_name(name: &str) -> Result<(), DuckyError> {
+    if is_plain_identifier(name) {
+        Ok(())
+    } else {
+        Err(DuckyError::QuerySyntaxError(format!(
+            "Invalid setting name: {}",
+            name
+        )))
+    }
+}
+
+/// Checks that a name only contains ASCII letters, digits and underscores,
+/// so it can be spliced into SQL without quoting.
+fn is_plain_identifier(name: &str) -> bool {
+    !name.is_empty() && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_')
+}
+
+/// Closes a database connection.
+///
+/// Runs on a dirty IO scheduler since it waits for any statement running on
+/// the connection.
+///
+/// The connection is released immediately rather than when the resource is
+/// garbage collected. Any later call using the resource fails with a
+/// "Connection closed" error. Closing an already closed connection is a
+/// no-op.
+///
+/// ## Arguments
+/// - `conn`: Connection resource to close
+///
+/// ## Returns
+/// - `Ok(())` on success
+/// - `Err(DuckyError)` if close fails
+#[rustler::nif(schedule = "DirtyIo")]
+fn close(conn: ResourceArc<ConnectionResource>) -> Result<rustler::Atom, DuckyError> {
+    let mut guard = conn.lock_slot();
+
+    if let Some(connection) = guard.take() {
+        if let Err((connection, e)) = connection.close() {
+            // Keep the connection usable if DuckDB refuses to close it
+            *guard = Some(connection);
+            return Err(e.into());
+        }
+    }
+    Ok(atoms::nil())
+}
+
+/// Interrupts the query currently running on the connection.
+///
+/// Doesn't take the connection lock, so it can be called from another
+/// process while `execute_query` is running. The interrupted call returns
+/// a `DatabaseError` whose message mentions the interruption. Does nothing
+/// if no query is running.
+///
+/// ## Arguments
+/// - `conn`: Connection resource
+///
+/// ## Returns
+/// - `Ok(nil)` always
+#[rustler::nif]
+fn interrupt(conn: ResourceArc<ConnectionResource>) -> Result<rustler::Atom, DuckyError> {
+    conn.interrupt.interrupt();
+    Ok(atoms::nil())
+}
+
+/// Checks that the connection is open and answers a trivial query.
+///
+/// Runs on a dirty IO scheduler since it waits for any statement running on
+/// the connection.
+///
+/// Unlike `test`, this goes through the connection, so pools can use it to
+/// validate a connection before handing it out.
+///
+/// ## Arguments
+/// - `conn`: Connection resource
+///
+/// ## Returns
+/// - `Ok(ok)` if the connection is usable
+/// - `Err(DuckyError)` if it's closed or the query fails
+#[rustler::nif(schedule = "DirtyIo")]
+fn ping(conn: ResourceArc<ConnectionResource>) -> Result<rustler::Atom, DuckyError> {
+    let connection = conn.lock()?;
+    connection.query_row("SELECT 1", [], |row| row.get::<_, i32>(0))?;
+    Ok(atoms::ok())
+}
+
+/// Starts sending `{query_log, sql, duration_micros}` to a process after
+/// every `execute_query` on the connection, whether it succeeded or not.
+///
+/// Runs on a dirty IO scheduler since it waits for any statement running on
+/// the connection.
+///
+/// Replaces any process set earlier. The message is sent from the process
+/// running the query, so it's delivered before `execute_query` returns.
+///
+/// ## Arguments
+/// - `conn`: Connection resource
+/// - `pid`: Process receiving the log messages
+///
+/// ## Returns
+/// - `Ok(nil)` on success
+/// - `Err(DatabaseError)` if the connection is closed
+#[rustler::nif(schedule = "DirtyIo")]
+fn enable_query_log(
+    conn: ResourceArc<ConnectionResource>,
+    pid: rustler::LocalPid,
+) -> Result<rustler::Atom, DuckyError> {
+    conn.lock()?;
+    conn.set_query_log(Some(pid));
+    Ok(atoms::nil())
+}
+
+/// Stops sending query log messages for the connection.
+///
+/// ## Arguments
+/// - `conn`: Connection resource
+///
+/// ## Returns
+/// - `Ok(nil)` always
+#[rustler::nif]
+fn disable_query_log(conn: ResourceArc<ConnectionResource>) -> Result<rustler::Atom, DuckyError> {
+    conn.set_query_log(None);
+    Ok(atoms::nil())
+}
+
+/// Begins a transaction on the connection.
+///
+/// Runs on a dirty IO scheduler since it waits for any statement running on
+/// the connection.
+///
+/// Transaction state belongs to the connection, so the matching `commit`
+/// or `rollback` must be called with the same connection resource.
+///
+/// ## Arguments
+/// - `conn`: Connection resource
+///
+/// ## Returns
+/// - `Ok(nil)` on success
+/// - `Err(DuckyError)` if a transaction is already open
+#[rustler::nif(schedule = "DirtyIo")]
+fn begin_transaction(conn: ResourceArc<ConnectionResource>) -> Result<rustler::Atom, DuckyError> {
+    execute_control(&conn, "BEGIN TRANSACTION")
+}
+
+/// Begins a read-only transaction on the connection.
+///
+/// Runs on a dirty IO scheduler since it waits for any statement running on
+/// the connection.
+///
+/// Statements that write, such as INSERT or CREATE, fail inside it, so
+/// analytical queries can't modify data by accident. It ends with `commit`
+/// or `rollback` like any other transaction.
+///
+/// ## Arguments
+/// - `conn`: Connection resource
+///
+/// ## Returns
+/// - `Ok(nil)` on success
+/// - `Err(DuckyError)` if a transaction is already open
+#[rustler::nif(schedule = "DirtyIo")]
+fn begin_read_only(conn: ResourceArc<ConnectionResource>) -> Result<rustler::Atom, DuckyError> {
+    execute_control(&conn, "BEGIN TRANSACTION READ ONLY")
+}
+
+/// Commits the transaction open on the connection.
+///
+/// Runs on a dirty IO scheduler since it waits for any statement running on
+/// the connection.
+///
+/// ## Arguments
+/// - `conn`: Connection resource the transaction was started on
+///
+/// ## Returns
+/// - `Ok(nil)` on success
+/// - `Err(DuckyError)` if no transaction is open or the commit fails
+#[rustler::nif(schedule = "DirtyIo")]
+fn commit(conn: ResourceArc<ConnectionResource>) -> Result<rustler::Atom, DuckyError> {
+    execute_control(&conn, "COMMIT")
+}
+
+/// Rolls back the transaction open on the connection.
+///
+/// Runs on a dirty IO scheduler since it waits for any statement running on
+/// the connection.
+///
+/// ## Arguments
+/// - `conn`: Connection resource the transaction was started on
+///
+/// ## Returns
+/// - `Ok(nil)` on success
+/// - `Err(DuckyError)` if no transaction is open
+#[rustler::nif(schedule = "DirtyIo")]
+fn rollback(conn: ResourceArc<ConnectionResource>) -> Result<rustler::Atom, DuckyError> {
+    execute_control(&conn, "ROLLBACK")
+}
+
+/// Returns a connection to a clean state, e.g. when a pool takes it back.
+///
+/// Resets exactly this state:
+/// - Rolls back any open transaction, including one started by running
+///   `BEGIN` through `execute_query`
+/// - Drops the connection's temporary tables, views and sequences
+/// - Resets session-scoped settings, such as `schema` and `search_path`,
+///   to their defaults, skipping the few DuckDB refuses to reset
+///
+/// Global settings, such as `threads` and `memory_limit`, are shared by
+/// every connection to the database and are left alone. Attached
+/// databases, loaded extensions and the statement cache are kept too.
+///
+/// ## Arguments
+/// - `conn`: Connection resource
+///
+/// ## Returns
+/// - `Ok(nil)` on success
+/// - `Err(DuckyError)` if the connection is closed or a step fails
+#[rustler::nif(schedule = "DirtyCpu")]
+fn reset(conn: ResourceArc<ConnectionResource>) -> Result<rustler::Atom, DuckyError> {
+    let connection = conn.lock()?;
+
+    match connection.execute_batch("ROLLBACK") {
+        Ok(()) => {}
+        Err(e) if e.to_string().contains("no transaction is active") => {}
+        Err(e) => return Err(e.into()),
+    }
+
+    let mut stmt = connection.prepare(
+        "SELECT 'VIEW', view_name FROM duckdb_views() WHERE temporary AND NOT internal \
+         UNION ALL SELECT 'TABLE', table_name FROM duckdb_tables() WHERE temporary \
+         UNION ALL SELECT 'SEQUENCE', sequence_name FROM duckdb_sequences() WHERE temporary",
+    )?;
+    let temporary = stmt
+        .query_map([], |row| Ok((row.get(0)?, row.get(1)?)))?
+        .collect::<Result<Vec<(String, String)>, _>>()?;
+    for (kind, name) in temporary {
+        connection.execute_batch(&format!(
+            "DROP {} IF EXISTS temp.main.{}",
+            kind,
+            quote_identifier(&name)
+        ))?;
+    }
+
+    let mut stmt =
+        connection.prepare("SELECT name FROM duckdb_settings() WHERE scope = 'LOCAL'")?;
+    let settings = stmt
+        .query_map([], |row| row.get(0))?
+        .collect::<Result<Vec<String>, _>>()?;
+    for name in settings {
+        // A few session settings refuse to be reset locally and are skipped
+        let _ = connection.execute_batch(&format!("RESET SESSION {}", quote_identifier(&name)));
+    }
+
+    Ok(atoms::nil())
+}
+
+/// Reports whether a transaction is open on the connection.
+///
+/// Runs on a dirty IO scheduler since it waits for any statement running on
+/// the connection.
+///
+/// DuckDB is asked directly, so transactions opened or ended by running
+/// `BEGIN`, `COMMIT` or `ROLLBACK` through `execute_query` count too.
+///
+/// ## Arguments
+/// - `conn`: Connection resource
+///
+/// ## Returns
+/// - `true` while a transaction is open
+/// - `false` otherwise, including once the connection is closed
+#[rustler::nif(schedule = "DirtyIo")]
+fn in_transaction(conn: ResourceArc<ConnectionResource>) -> bool {
+    conn.lock()
+        .is_ok_and(|connection| transaction_open(&connection).unwrap_or(false))
+}
+
+/// Runs a statement that produces no result set, such as transaction control.
+fn execute_control(conn: &ConnectionResource, sql: &str) -> Result<rustler::Atom, DuckyError> {
+    let connection = conn.lock()?;
+
+    connection.execute(sql, [])?;
+    Ok(atoms::nil())
+}
+
+/// Runs a list of statements in one transaction.
+///
+/// Runs on a dirty CPU scheduler to avoid blocking the BEAM VM.
+///
+/// The transaction is committed if every statement succeeds. On the first
+/// failure it is rolled back and the remaining statements are skipped, so
+/// callers can't forget to roll back.
+///
+/// ## Arguments
+/// - `env`: NIF environment for term creation
+/// - `conn`: Connection resource
+/// - `statements`: List of `{sql, params}` tuples, run in order
+///
+/// ## Returns
+/// - `{ok, [{columns, rows}]}` with one result per statement
+/// - `{error, {index, reason}}` naming the 1-based index of the failing
+///   statement and its error reason
+/// - `{error, reason}` if the transaction can't be started or committed
+#[rustler::nif(schedule = "DirtyCpu")]
+fn with_transaction<'a>(
+    env: Env<'a>,
+    conn: ResourceArc<ConnectionResource>,
+    statements: Vec<(String, Vec<Term<'a>>)>,
+) -> NifResult<Term<'a>> {
+    let connection = match conn.lock() {
+        Ok(connection) => connection,
+        Err(e) => return Ok(e.encode(env)),
+    };
+    let _caller = CallerMonitor::new(env, &conn);
+
+    if let Err(e) = connection.execute("BEGIN TRANSACTION", []) {
+        return Ok(DuckyError::from(e).encode(env));
+    }
+
+    let mut results = Vec::with_capacity(statements.len());
+    for (index, (sql, params_list)) in statements.into_iter().enumerate() {
+        let result = bind_params(&sql, params_list).and_then(|(sql, params)| {
+            execute_statement(env, &connection, &sql, &param_refs(&params))
+        });
+        match result {
+            Ok(result) => results.push(result),
+            Err(e) => {
+                let _ = connection.execute("ROLLBACK", []);
+                return Ok((atoms::error(), (index + 1, e.reason(env))).encode(env));
+            }
+        }
+    }
+
+    if let Err(e) = connection.execute("COMMIT", []) {
+        let _ = connection.execute("ROLLBACK", []);
+        return Ok(DuckyError::from(e).encode(env));
+    }
+    Ok((atoms::ok(), results).encode(env))
+}
+
+/// Writes the write-ahead log into the database file.
+///
+/// Runs on a dirty IO scheduler since the WAL is flushed to disk. Call it
+/// before copying a database file for a backup. Does nothing for
+/// in-memory databases.
+///
+/// ## Arguments
+/// - `conn`: Connection resource
+///
+/// ## Returns
+/// - `Ok(nil)` on success
+/// - `Err(DatabaseError)` if other transactions are open, in which case
+///   `force_checkpoint` can be used instead
+#[rustler::nif(schedule = "DirtyIo")]
+fn checkpoint(conn: ResourceArc<ConnectionResource>) -> Result<rustler::Atom, DuckyError> {
+    run_checkpoint(&conn, "CHECKPOINT")
+}
+
+/// Writes the write-ahead log into the database file, aborting any other
+/// open transactions that would block it.
+///
+/// Runs on a dirty IO scheduler since the WAL is flushed to disk.
+///
+/// ## Arguments
+/// - `conn`: Connection resource
+///
+/// ## Returns
+/// - `Ok(nil)` on success
+/// - `Err(DatabaseError)` if the checkpoint fails
+#[rustler::nif(schedule = "DirtyIo")]
+fn force_checkpoint(conn: ResourceArc<ConnectionResource>) -> Result<rustler::Atom, DuckyError> {
+    run_checkpoint(&conn, "FORCE CHECKPOINT")
+}
+
+/// Runs a checkpoint statement, explaining failures caused by transactions.
+fn run_checkpoint(conn: &ConnectionResource, sql: &str) -> Result<rustler::Atom, DuckyError> {
+    let connection = conn.lock()?;
+
+    connection.execute(sql, []).map_err(|e| {
+        let msg = e.to_string();
+        if msg.contains("Cannot CHECKPOINT") {
+            DuckyError::DatabaseError(format!(
+                "Checkpoint blocked by open transactions, commit them or use force_checkpoint: {}",
+                msg
+            ))
+        } else {
+            e.into()
+        }
+    })?;
+    Ok(atoms::nil())
+}
+
+/// Recomputes table statistics used by the query optimizer.
+///
+/// Runs on a dirty CPU scheduler since every table is scanned. Worth
+/// running after large loads so query plans reflect the new data.
+///
+/// ## Arguments
+/// - `conn`: Connection resource
+///
+/// ## Returns
+/// - `Ok(nil)` on success
+/// - `Err(DatabaseError)` if the statistics can't be updated
+#[rustler::nif(schedule = "DirtyCpu")]
+fn analyze(conn: ResourceArc<ConnectionResource>) -> Result<rustler::Atom, DuckyError> {
+    run_maintenance(&conn, "ANALYZE")
+}
+
+/// Runs `VACUUM` to reclaim space.
+///
+/// Runs on a dirty CPU scheduler like `analyze`. DuckDB mostly reclaims
+/// the space of deleted rows when checkpointing, so depending on the
+/// version `VACUUM` may do little beyond that.
+///
+/// ## Arguments
+/// - `conn`: Connection resource
+///
+/// ## Returns
+/// - `Ok(nil)` on success
+/// - `Err(DatabaseError)` if the storage version doesn't support it
+#[rustler::nif(schedule = "DirtyCpu")]
+fn vacuum(conn: ResourceArc<ConnectionResource>) -> Result<rustler::Atom, DuckyError> {
+    run_maintenance(&conn, "VACUUM")
+}
+
+/// Runs a maintenance statement, explaining failures caused by features
+/// the DuckDB version or storage format doesn't implement.
+fn run_maintenance(conn: &ConnectionResource, sql: &str) -> Result<rustler::Atom, DuckyError> {
+    let connection = conn.lock()?;
+
+    connection.execute(sql, []).map_err(|e| {
+        let msg = e.to_string();
+        if msg.contains("Not implemented Error") {
+            DuckyError::DatabaseError(format!(
+                "{} is not supported by this DuckDB version: {}",
+                sql, msg
+            ))
+        } else {
+            e.into()
+        }
+    })?;
+    Ok(atoms::nil())
+}
+
+/// Reports storage statistics for the connection's current database.
+///
+/// Runs on a dirty IO scheduler since it waits for any statement running on
+/// the connection.
+///
+/// Reads `pragma_database_size`. DuckDB formats sizes for humans, e.g.
+/// `"1.5 MiB"`, so they are converted back to bytes, accurate to a tenth
+/// of the unit. Values that aren't sizes, like a `"memory_limit"` of
+/// `"Unlimited"`, are kept as strings. In-memory databases report zero
+/// sizes and a null `"wal_size"`.
+///
+/// ## Arguments
+/// - `env`: NIF environment for term creation
+/// - `conn`: Connection resource
+///
+/// ## Returns
+/// - `Ok(map)` with `"database_size"`, `"block_size"`, `"total_blocks"`,
+///   `"used_blocks"`, `"free_blocks"`, `"wal_size"`, `"memory_usage"` and
+///   `"memory_limit"`
+/// - `Err(DuckyError)` on failure
+#[rustler::nif(schedule = "DirtyIo")]
+fn database_size<'a>(
+    env: Env<'a>,
+    conn: ResourceArc<ConnectionResource>,
+) -> Result<Term<'a>, DuckyError> {
+    use rustler::types::map::map_new;
+
+    let connection = conn.lock()?;
+    let mut stmt = connection.prepare(
+        "SELECT database_size, block_size, total_blocks, used_blocks, free_blocks, \
+         wal_size, memory_usage, memory_limit \
+         FROM pragma_database_size() WHERE database_name = current_database()",
+    )?;
+    let mut rows = stmt.query([])?;
+    let row = rows
+        .next()?
+        .ok_or_else(|| DuckyError::DatabaseError("No current database".to_string()))?;
+
+    let size = |index: usize| -> Result<Term<'a>, DuckyError> {
+        Ok(match row.get::<_, Option<String>>(index)? {
+            Some(text) => match parse_byte_size(&text) {
+                Some(bytes) => bytes.encode(env),
+                None => text.encode(env),
+            },
+            None => atoms::null().encode(env),
+        })
+    };
+    let count = |index: usize| -> Result<Term<'a>, DuckyError> {
+        Ok(row.get::<_, i64>(index)?.encode(env))
+    };
+
+    [
+        ("database_size", size(0)?),
+        ("block_size", count(1)?),
+        ("total_blocks", count(2)?),
+        ("used_blocks", count(3)?),
+        ("free_blocks", count(4)?),
+        ("wal_size", size(5)?),
+        ("memory_usage", size(6)?),
+        ("memory_limit", size(7)?),
+    ]
+    .into_iter()
+    .try_fold(map_new(env), |map, (key, value)| {
+        map.map_put(key.encode(env), value)
+    })
+    .map_err(|_| DuckyError::DatabaseError("Failed to build size map".to_string()))
+}
+
+/// Converts a size such as `"1.5 MiB"` or `"512 bytes"`, as formatted by
+/// DuckDB, back into bytes.
+fn parse_byte_size(size: &str) -> Option<u64> {
+    let (number, unit) = size.split_once(' ')?;
+    let multiplier: u64 = match unit {
+        "byte" | "bytes" => 1,
+        "KiB" => 1 << 10,
+        "MiB" => 1 << 20,
+        "GiB" => 1 << 30,
+        "TiB" => 1 << 40,
+        "PiB" => 1 << 50,
+        "kB" => 1_000,
+        "MB" => 1_000_000,
+        "GB" => 1_000_000_000,
+        "TB" => 1_000_000_000_000,
+        "PB" => 1_000_000_000_000_000,
+        _ => return None,
+    };
+    let number: f64 = number.parse().ok()?;
+    Some((number * multiplier as f64) as u64)
+}
+
+/// Executes a SQL query with optional parameter binding.
+///
+/// Runs on a dirty CPU scheduler to avoid blocking the BEAM VM.
+///
+/// Handles both result-returning queries (SELECT, SHOW, etc.) and
+/// non-result statements (CREATE, INSERT, UPDATE, DELETE, etc.).
+/// The query is interrupted if the calling process dies before it finishes.
+/// Statements are taken from the connection's statement cache, so running
+/// the same SQL text again skips parsing and planning.
+/// Each call is reported to the process set with `enable_query_log`.
+///
+/// ## Arguments
+/// - `env`: NIF environment for term creation
+/// - `conn`: Connection resource
+/// - `sql`: SQL query string with optional `?` placeholders
+/// - `params_list`: Parameter values to bind (empty for non-parameterized
+///   queries), matched to `?` placeholders in the order they appear in the
+///   SQL text, including placeholders inside CTEs and subqueries
+///
+/// ## Returns
+/// - `Ok({columns, rows})` where columns is a list of column names
+///   and rows is a list of rows (each row is a list of values)
+/// - For DDL/DML statements, returns empty columns and rows
+/// - `Err(DuckyError)` on failure
+#[rustler::nif(schedule = "DirtyCpu")]
+fn execute_query<'a>(
+    env: Env<'a>,
+    conn: ResourceArc<ConnectionResource>,
+    sql: String,
+    params_list: Vec<Term<'a>>,
+) -> Result<(Vec<String>, Vec<Vec<Term<'a>>>), DuckyError> {
+    let connection = conn.lock()?;
+    let _caller = CallerMonitor::new(env, &conn);
+
+    // Convert Erlang terms to DuckDB params
+    let (sql, params) = bind_params(&sql, params_list)?;
+
+    let started = Instant::now();
+    let result = execute_cached_statement(env, &connection, &sql, &param_refs(&params));
+    conn.log_query(env, &sql, started.elapsed());
+    result
+}
+
+/// Executes a SQL query like `execute_query`, with options.
+///
+/// Runs on a dirty CPU scheduler to avoid blocking the BEAM VM.
+///
+/// ## Arguments
+/// - `env`: NIF environment for term creation
+/// - `conn`: Connection resource
+/// - `sql`: SQL query string with optional `?` placeholders
+/// - `params_list`: Parameter values to bind
+/// - `options`: Query options
+///   - `"positional_names"`: When `true`, columns with an empty name or a
+///     name already used by an earlier column are named `"column_N"` after
+///     their zero-based position. DuckDB's generated names for unaliased
+///     expressions vary between versions, so this keeps keys stable.
+///   - `"integers_as_strings"`: When `true`, integer columns of any width
+///     are returned as decimal strings. Building Erlang integers, bignums
+///     above all, is a noticeable share of the cost of wide results that
+///     are only formatted for display. Integers nested in lists, structs
+///     and maps are unchanged.
+///
+/// ## Returns
+/// - `Ok({columns, rows})` as for `execute_query`
+/// - `Err(QuerySyntaxError)` if an option is invalid
+/// - `Err(DuckyError)` on other failures
+#[rustler::nif(schedule = "DirtyCpu")]
+fn execute_query_opts<'a>(
+    env: Env<'a>,
+    conn: ResourceArc<ConnectionResource>,
+    sql: String,
+    params_list: Vec<Term<'a>>,
+    options: HashMap<String, Term<'a>>,
+) -> Result<(Vec<String>, Vec<Vec<Term<'a>>>), DuckyError> {
+    let options = QueryOptions::parse(&options)?;
+
+    let connection = conn.lock()?;
+    let _caller = CallerMonitor::new(env, &conn);
+
+    let (sql, params) = bind_params(&sql, params_list)?;
+    let started = Instant::now();
+    let result = with_cached_statement(&connection, &sql, |stmt| {
+        run_statement_up_to(
+            env,
+            stmt,
+            &param_refs(&params),
+            usize::MAX,
+            options.encoding,
+            None,
+        )
+    });
+    conn.log_query(env, &sql, started.elapsed());
+    let (columns, rows, _) = result?;
+
+    Ok((options.column_names(columns), rows))
+}
+
+/// Executes a SQL query like `execute_query_opts`, timing each phase.
+///
+/// Runs on a dirty CPU scheduler to avoid blocking the BEAM VM.
+///
+/// The timings cover getting the statement from the connection's statement
+/// cache (preparing it on a miss), executing it, and converting the rows to
+/// Erlang terms. Taking the timings costs a few clock reads, but
+/// `execute_query` skips them entirely.
+///
+/// ## Arguments
+/// - `env`: NIF environment for term creation
+/// - `conn`: Connection resource
+/// - `sql`: SQL query string with optional `?` placeholders
+/// - `params_list`: Parameter values to bind
+/// - `options`: Query options, as for `execute_query_opts`
+///
+/// ## Returns
+/// - `Ok({columns, rows, timing})` where columns and rows are as for
+///   `execute_query_opts` and timing maps `"prepare"`, `"execute"` and
+///   `"fetch"` to durations in microseconds
+/// - `Err(QuerySyntaxError)` if an option is invalid
+/// - `Err(DuckyError)` on other failures
+#[rustler::nif(schedule = "DirtyCpu")]
+fn execute_query_timed<'a>(
+    env: Env<'a>,
+    conn: ResourceArc<ConnectionResource>,
+    sql: String,
+    params_list: Vec<Term<'a>>,
+    options: HashMap<String, Term<'a>>,
+) -> Result<(Vec<String>, Vec<Vec<Term<'a>>>, HashMap<&'static str, u64>), DuckyError> {
+    let options = QueryOptions::parse(&options)?;
+
+    let connection = conn.lock()?;
+    let _caller = CallerMonitor::new(env, &conn);
+
+    let (sql, params) = bind_params(&sql, params_list)?;
+    let mut timing = StatementTiming::default();
+    let started = Instant::now();
+    let result = with_cached_statement(&connection, &sql, |stmt| {
+        timing.prepare = started.elapsed();
+        run_statement_up_to(
+            env,
+            stmt,
+            &param_refs(&params),
+            usize::MAX,
+            options.encoding,
+            Some(&mut timing),
+        )
+    });
+    conn.log_query(env, &sql, started.elapsed());
+    let (columns, rows, _) = result?;
+
+    Ok((options.column_names(columns), rows, timing.to_micros()))
+}
+
+/// Executes a SQL query like `execute_query`, returning at most `max_rows`
+/// rows.
+///
+/// Runs on a dirty CPU scheduler to avoid blocking the BEAM VM.
+///
+/// Fetching stops once the limit is reached, so a query that would return
+/// far more rows than expected can't exhaust memory. Unlike adding a
+/// `LIMIT` clause, this works for any SQL text.
+///
+/// ## Arguments
+/// - `env`: NIF environment for term creation
+/// - `conn`: Connection resource
+/// - `sql`: SQL query string with optional `?` placeholders
+/// - `params_list`: Parameter values to bind
+/// - `max_rows`: Maximum number of rows to return
+///
+/// ## Returns
+/// - `Ok({columns, rows, truncated})` if the result had more rows
+/// - `Ok({columns, rows, complete})` if every row was returned
+/// - `Err(DuckyError)` on failure
+#[rustler::nif(schedule = "DirtyCpu")]
+fn execute_query_limited<'a>(
+    env: Env<'a>,
+    conn: ResourceArc<ConnectionResource>,
+    sql: String,
+    params_list: Vec<Term<'a>>,
+    max_rows: usize,
+) -> Result<(Vec<String>, Vec<Vec<Term<'a>>>, rustler::Atom), DuckyError> {
+    let connection = conn.lock()?;
+    let _caller = CallerMonitor::new(env, &conn);
+
+    let (sql, params) = bind_params(&sql, params_list)?;
+    let (columns, rows, truncated) = with_cached_statement(&connection, &sql, |stmt| {
+        run_statement_up_to(
+            env,
+            stmt,
+            &param_refs(&params),
+            max_rows,
+            RowEncoding::default(),
+            None,
+        )
+    })?;
+
+    let status = if truncated {
+        atoms::truncated()
+    } else {
+        atoms::complete()
+    };
+    Ok((columns, rows, status))
+}
+
+/// Executes a SQL query, interrupting it if it runs longer than a timeout.
+///
+/// Runs on a dirty CPU scheduler to avoid blocking the BEAM VM.
+///
+/// A shared timer thread interrupts the connection once the timeout
+/// expires. The deadline is cancelled before the call returns, so it can't
+/// interrupt a later query on the same connection.
+///
+/// ## Arguments
+/// - `env`: NIF environment for term creation
+/// - `conn`: Connection resource
+/// - `sql`: SQL query string with optional `?` placeholders
+/// - `params_list`: Parameter values to bind
+/// - `timeout_ms`: Maximum running time in milliseconds
+///
+/// ## Returns
+/// - `Ok({columns, rows})` as for `execute_query`
+/// - `Err(Timeout)` encoded as `{error, {timeout, TimeoutMs}}` if the query
+///   was interrupted
+/// - `Err(DuckyError)` on other failures, including ones raised by a query
+///   that failed for another reason as its timeout expired
+#[rustler::nif(schedule = "DirtyCpu")]
+fn execute_query_timeout<'a>(
+    env: Env<'a>,
+    conn: ResourceArc<ConnectionResource>,
+    sql: String,
+    params_list: Vec<Term<'a>>,
+    timeout_ms: u64,
+) -> Result<(Vec<String>, Vec<Vec<Term<'a>>>), DuckyError> {
+    let connection = conn.lock()?;
+    let _caller = CallerMonitor::new(env, &conn);
+
+    let (sql, params) = bind_params(&sql, params_list)?;
+
+    let timer = InterruptTimer::shared();
+    let deadline = timer.schedule(
+        Duration::from_millis(timeout_ms),
+        Arc::clone(&conn.interrupt),
+    );
+
+    let result = execute_statement(env, &connection, &sql, &param_refs(&params));
+
+    let expired = deadline.is_some_and(|id| timer.cancel(id));
+    match result {
+        Err(e) if expired && e.is_interrupt() => Err(DuckyError::Timeout(timeout_ms)),
+        result => result,
+    }
+}
+
+/// Executes a SQL query with named parameter binding.
+///
+/// Runs on a dirty CPU scheduler to avoid blocking the BEAM VM.
+///
+/// Each placeholder in the statement (e.g. `$id`) is looked up in the
+/// parameter map by its name without the `$` prefix. Keys that don't
+/// correspond to a placeholder are ignored.
+///
+/// ## Arguments
+/// - `env`: NIF environment for term creation
+/// - `conn`: Connection resource
+/// - `sql`: SQL query string with named placeholders
+/// - `params_map`: Map of placeholder name to value
+///
+/// ## Returns
+/// - `Ok({columns, rows})` with the same shape as `execute_query`
+/// - `Err(QuerySyntaxError)` if a placeholder has no matching key
+/// - `Err(DuckyError)` on any other failure
+#[rustler::nif(schedule = "DirtyCpu")]
+fn execute_query_named<'a>(
+    env: Env<'a>,
+    conn: ResourceArc<ConnectionResource>,
+    sql: String,
+    params_map: HashMap<String, Term<'a>>,
+) -> Result<(Vec<String>, Vec<Vec<Term<'a>>>), DuckyError> {
+    let connection = conn.lock()?;
+    let _caller = CallerMonitor::new(env, &conn);
+
+    // Values are converted up front to know which placeholders to cast,
+    // but conversion errors are only reported for parameters in use
+    let mut casts = HashMap::new();
+    let mut values = HashMap::with_capacity(params_map.len());
+    for (name, term) in params_map {
+        let value = bound_param(term).map(|(value, sql_type)| {
+            if let Some(sql_type) = sql_type {
+                casts.insert(name.clone(), sql_type);
+            }
+            value
+        });
+        values.insert(name, value);
+    }
+
+    let sql = cast_placeholders(&sql, &casts);
+    with_cached_statement(&connection, &sql, |stmt| {
+        // Placeholder indices are 1-based
+        let mut params = Vec::with_capacity(stmt.parameter_count());
+        for idx in 1..=stmt.parameter_count() {
+            let name = stmt.parameter_name(idx)?;
+            let value = values.remove(&name).ok_or_else(|| {
+                DuckyError::QuerySyntaxError(format!("No value given for parameter ${}", name))
+            })?;
+            params.push(value.map_err(|e| e.for_parameter(&format!("${}", name)))?);
+        }
+
+        run_statement(env, stmt, &param_refs(&params))
+    })
+}
+
+/// Executes a SQL query and reports the declared type of each column.
+///
+/// Runs on a dirty CPU scheduler to avoid blocking the BEAM VM.
+///
+/// Column types come from the result schema rather than the row values,
+/// so empty and all-NULL results still report accurate types.
+///
+/// ## Arguments
+/// - `env`: NIF environment for term creation
+/// - `conn`: Connection resource
+/// - `sql`: SQL query string with optional `?` placeholders
+/// - `params_list`: Parameter values to bind
+///
+/// ## Returns
+/// - `Ok({columns, rows})` where columns is a list of `{name, type_atom}`
+/// - For DDL/DML statements, the columns and rows DuckDB reports for them
+/// - `Err(DuckyError)` on failure
+#[rustler::nif(schedule = "DirtyCpu")]
+fn execute_query_typed<'a>(
+    env: Env<'a>,
+    conn: ResourceArc<ConnectionResource>,
+    sql: String,
+    params_list: Vec<Term<'a>>,
+) -> Result<(Vec<(String, rustler::Atom)>, Vec<Vec<Term<'a>>>), DuckyError> {
+    let connection = conn.lock()?;
+    let _caller = CallerMonitor::new(env, &conn);
+
+    let (sql, params) = bind_params(&sql, params_list)?;
+    let refs = param_refs(&params);
+
+    with_cached_statement(&connection, &sql, |stmt| {
+        let mut rows_result = stmt.query(refs.as_slice())?;
+        let raw_rows = collect_rows(env, &mut rows_result)?;
+
+        // The result schema is only known once the statement has run
+        let mut columns = Vec::with_capacity(stmt.column_count());
+        for i in 0..stmt.column_count() {
+            let name = stmt.column_name(i)?.to_string();
+            columns.push((name, data_type_atom(&stmt.column_type(i))));
+        }
+
+        Ok((columns, raw_rows))
+    })
+}
+
+/// Executes a SQL query and returns each row as a map keyed by column name.
+///
+/// Runs on a dirty CPU scheduler to avoid blocking the BEAM VM.
+///
+/// Duplicate column names, as produced by joins, are made unique by
+/// suffixing later occurrences with `_2`, `_3`, and so on.
+///
+/// ## Arguments
+/// - `env`: NIF environment for term creation
+/// - `conn`: Connection resource
+/// - `sql`: SQL query string with optional `?` placeholders
+/// - `params_list`: Parameter values to bind
+///
+/// ## Returns
+/// - `Ok([row_map])` with one map per row
+/// - For DDL/DML statements, returns an empty list
+/// - `Err(DuckyError)` on failure
+#[rustler::nif(schedule = "DirtyCpu")]
+fn execute_query_maps<'a>(
+    env: Env<'a>,
+    conn: ResourceArc<ConnectionResource>,
+    sql: String,
+    params_list: Vec<Term<'a>>,
+) -> Result<Vec<Term<'a>>, DuckyError> {
+    let connection = conn.lock()?;
+    let _caller = CallerMonitor::new(env, &conn);
+
+    let (sql, params) = bind_params(&sql, params_list)?;
+    let (columns, rows) = execute_statement(env, &connection, &sql, &param_refs(&params))?;
+
+    rows_to_maps(env, &disambiguate_columns(columns), rows)
+}
+
+/// Executes a query that returns at most one value, such as a count.
+///
+/// Runs on a dirty CPU scheduler to avoid blocking the BEAM VM.
+///
+/// ## Arguments
+/// - `env`: NIF environment for term creation
+/// - `conn`: Connection resource
+/// - `sql`: SQL query string with optional `?` placeholders
+/// - `params_list`: Parameter values to bind
+///
+/// ## Returns
+/// - `Ok(value)` for a single row with a single column
+/// - `Ok(nil)` if the query returns no rows
+/// - `Err(DatabaseError)` if it returns several rows or columns
+#[rustler::nif(schedule = "DirtyCpu")]
+fn query_scalar<'a>(
+    env: Env<'a>,
+    conn: ResourceArc<ConnectionResource>,
+    sql: String,
+    params_list: Vec<Term<'a>>,
+) -> Result<Term<'a>, DuckyError> {
+    let connection = conn.lock()?;
+    let _caller = CallerMonitor::new(env, &conn);
+
+    let (sql, params) = bind_params(&sql, params_list)?;
+    let (_, rows) = execute_statement(env, &connection, &sql, &param_refs(&params))?;
+
+    match single_row(rows)? {
+        None => Ok(atoms::nil().encode(env)),
+        Some(row) => match <[Term; 1]>::try_from(row) {
+            Ok([value]) => Ok(value),
+            Err(row) => Err(DuckyError::DatabaseError(format!(
+                "Expected a single column, got {}",
+                row.len()
+            ))),
+        },
+    }
+}
+
+/// Executes a query that returns at most one row, as a map keyed by
+/// column name.
+///
+/// Runs on a dirty CPU scheduler to avoid blocking the BEAM VM.
+///
+/// ## Arguments
+/// - `env`: NIF environment for term creation
+/// - `conn`: Connection resource
+/// - `sql`: SQL query string with optional `?` placeholders
+/// - `params_list`: Parameter values to bind
+///
+/// ## Returns
+/// - `Ok(row_map)` for a single row
+/// - `Ok(nil)` if the query returns no rows
+/// - `Err(DatabaseError)` if it returns several rows
+#[rustler::nif(schedule = "DirtyCpu")]
+fn query_one<'a>(
+    env: Env<'a>,
+    conn: ResourceArc<ConnectionResource>,
+    sql: String,
+    params_list: Vec<Term<'a>>,
+) -> Result<Term<'a>, DuckyError> {
+    let connection = conn.lock()?;
+    let _caller = CallerMonitor::new(env, &conn);
+
+    let (sql, params) = bind_params(&sql, params_list)?;
+    let (columns, rows) = execute_statement(env, &connection, &sql, &param_refs(&params))?;
+
+    match single_row(rows)? {
+        None => Ok(atoms::nil().encode(env)),
+        Some(row) => {
+            let mut maps = rows_to_maps(env, &disambiguate_columns(columns), vec![row])?;
+            Ok(maps.remove(0))
+        }
+    }
+}
+
+/// Takes the only row of a result, failing if there is more than one.
+fn single_row<T>(rows: Vec<T>) -> Result<Option<T>, DuckyError> {
+    if rows.len() > 1 {
+        return Err(DuckyError::DatabaseError(format!(
+            "Expected at most one row, got {}",
+            rows.len()
+        )));
+    }
+    Ok(rows.into_iter().next())
+}
+
+/// Prepares a statement for repeated execution.
+///
+/// ## Arguments
+/// - `conn`: Connection resource
+/// - `sql`: SQL query string with optional `?` placeholders
+///
+/// ## Returns
+/// - `Ok(ResourceArc<PreparedStatementResource>)` on success
+/// - `Err(DuckyError)` if the statement doesn't prepare
+#[rustler::nif(schedule = "DirtyCpu")]
+fn prepare(
+    conn: ResourceArc<ConnectionResource>,
+    sql: String,
+) -> Result<ResourceArc<PreparedStatementResource>, DuckyError> {
+    {
+        let connection = conn.lock()?;
+
+        // Surfaces errors now and warms the cache for the first execution.
+        // Preparing a script would run all but its last statement, so
+        // those are left for `execute_prepared`.
+        if split_statements(&sql).len() <= 1 {
+            connection.prepare_cached(&sql)?;
+        }
+    }
+
+    Ok(ResourceArc::new(PreparedStatementResource { conn, sql }))
+}
+
+/// Executes a prepared statement with a new set of parameters.
+///
+/// Runs on a dirty CPU scheduler to avoid blocking the BEAM VM.
+///
+/// ## Arguments
+/// - `env`: NIF environment for term creation
+/// - `stmt`: Prepared statement resource
+/// - `params_list`: Parameter values to bind
+///
+/// ## Returns
+/// - `Ok({columns, rows})` with the same shape as `execute_query`
+/// - `Err(DuckyError)` on failure
+#[rustler::nif(schedule = "DirtyCpu")]
+fn execute_prepared<'a>(
+    env: Env<'a>,
+    stmt: ResourceArc<PreparedStatementResource>,
+    params_list: Vec<Term<'a>>,
+) -> Result<(Vec<String>, Vec<Vec<Term<'a>>>), DuckyError> {
+    let connection = stmt.conn.lock()?;
+    let _caller = CallerMonitor::new(env, &stmt.conn);
+
+    let (sql, params) = bind_params(&stmt.sql, params_list)?;
+    with_cached_statement(&connection, &sql, |cached| {
+        run_statement(env, cached, &param_refs(&params))
+    })
+}
+
+/// Sets how many prepared statements a connection keeps cached.
+///
+/// Runs on a dirty IO scheduler since it waits for any statement running on
+/// the connection.
+///
+/// `execute_query` and prepared statement handles share the connection's
+/// LRU statement cache, keyed by SQL text. The cache lives inside the
+/// `duckdb::Connection`, which sidesteps `Statement` borrowing the
+/// connection, at the cost of a lookup by SQL text on every call. Queries
+/// whose text changes on every call (e.g. values spliced in instead of
+/// bound) only churn the cache; a size of 0 turns caching off.
+///
+/// ## Arguments
+/// - `conn`: Connection resource
+/// - `size`: Maximum number of cached statements
+///
+/// ## Returns
+/// - `Ok(nil)` on success
+/// - `Err(DuckyError)` if the connection is closed
+#[rustler::nif(schedule = "DirtyIo")]
+fn set_statement_cache_size(
+    conn: ResourceArc<ConnectionResource>,
+    size: usize,
+) -> Result<rustler::Atom, DuckyError> {
+    let connection = conn.lock()?;
+    connection.set_prepared_statement_cache_capacity(size);
+    Ok(atoms::nil())
+}
+
+/// Drops every cached prepared statement on a connection.
+///
+/// Runs on a dirty IO scheduler since it waits for any statement running on
+/// the connection.
+///
+/// Prepared statement handles stay valid and are prepared again on their
+/// next execution.
+///
+/// ## Arguments
+/// - `conn`: Connection resource
+///
+/// ## Returns
+/// - `Ok(nil)` on success
+/// - `Err(DuckyError)` if the connection is closed
+#[rustler::nif(schedule = "DirtyIo")]
+fn clear_statement_cache(
+    conn: ResourceArc<ConnectionResource>,
+) -> Result<rustler::Atom, DuckyError> {
+    let connection = conn.lock()?;
+    connection.flush_prepared_statement_cache();
+    Ok(atoms::nil())
+}
+
+/// Executes a data-modifying statement and reports how many rows it changed.
+///
+/// Runs on a dirty CPU scheduler to avoid blocking the BEAM VM.
+///
+/// ## Arguments
+/// - `conn`: Connection resource
+/// - `sql`: INSERT, UPDATE or DELETE statement with optional `?` placeholders
+/// - `params_list`: Parameter values to bind
+///
+/// ## Returns
+/// - `Ok(rows_changed)` on success
+/// - `Err(DuckyError)` on failure
+#[rustler::nif(schedule = "DirtyCpu")]
+fn execute_dml<'a>(
+    env: Env<'a>,
+    conn: ResourceArc<ConnectionResource>,
+    sql: String,
+    params_list: Vec<Term<'a>>,
+) -> Result<usize, DuckyError> {
+    let connection = conn.lock()?;
+    let _caller = CallerMonitor::new(env, &conn);
+
+    let (sql, params) = bind_params(&sql, params_list)?;
+    with_cached_statement(&connection, &sql, |stmt| {
+        Ok(stmt.execute(param_refs(&params).as_slice())?)
+    })
+}
+
+/// Creates a temporary table holding the result of a query.
+///
+/// Runs on a dirty CPU scheduler to avoid blocking the BEAM VM.
+///
+/// The table lives until the connection is closed or it's dropped, and is
+/// only visible to this connection.
+///
+/// ## Arguments
+/// - `conn`: Connection resource
+/// - `table_name`: Name of the table to create
+/// - `sql`: SQL query string with optional `?` placeholders
+/// - `params_list`: Parameter values to bind into the query
+///
+/// ## Returns
+/// - `Ok(rows)` with the number of rows in the new table
+/// - `Err(QuerySyntaxError)` if the table name is invalid
+/// - `Err(DatabaseError)` if the query fails or the table already exists
+#[rustler::nif(schedule = "DirtyCpu")]
+fn create_temp_table_as<'a>(
+    env: Env<'a>,
+    conn: ResourceArc<ConnectionResource>,
+    table_name: String,
+    sql: String,
+    params_list: Vec<Term<'a>>,
+) -> Result<usize, DuckyError> {
+    if !is_plain_identifier(&table_name) {
+        return Err(DuckyError::QuerySyntaxError(format!(
+            "Invalid table name: {}",
+            table_name
+        )));
+    }
+
+    reject_empty_statement(&sql)?;
+    let (sql, params) = bind_params(&sql, params_list)?;
+    let create = format!("CREATE TEMP TABLE {} AS ({})", table_name, sql);
+
+    let connection = conn.lock()?;
+    let _caller = CallerMonitor::new(env, &conn);
+
+    with_statement(&connection, &create, |stmt| {
+        Ok(stmt.execute(param_refs(&params).as_slice())?)
+    })
+}
+
+/// Executes a statement once for each set of parameters.
+///
+/// Runs on a dirty CPU scheduler to avoid blocking the BEAM VM.
+///
+/// The statement is prepared once and every execution runs under a single
+/// lock, inside a transaction so either all parameter sets are applied or
+/// none are. If a transaction is already open, whether from
+/// `begin_transaction` or a `BEGIN` statement, the executions join it
+/// instead, and a failure leaves that transaction for the caller to roll
+/// back.
+///
+/// ## Arguments
+/// - `env`: NIF environment, used to interrupt the executions if the
+///   caller dies
+/// - `conn`: Connection resource
+/// - `sql`: Statement with `?` placeholders, typically an INSERT
+/// - `param_sets`: List of parameter lists, one per execution
+///
+/// ## Returns
+/// - `Ok(rows_changed)` summed over all executions
+/// - `Err(DuckyError)` on the first failure, after rolling back
+#[rustler::nif(schedule = "DirtyCpu")]
+fn execute_many<'a>(
+    env: Env<'a>,
+    conn: ResourceArc<ConnectionResource>,
+    sql: String,
+    param_sets: Vec<Vec<Term<'a>>>,
+) -> Result<usize, DuckyError> {
+    let executions = param_sets
+        .into_iter()
+        .map(|params_list| bind_params(&sql, params_list))
+        .collect::<Result<Vec<_>, DuckyError>>()?;
+
+    let connection = conn.lock()?;
+    let _caller = CallerMonitor::new(env, &conn);
+    let own_transaction = !transaction_open(&connection)?;
+    if own_transaction {
+        connection.execute("BEGIN TRANSACTION", [])?;
+    }
+
+    let result = execute_each(&connection, &executions);
+    if own_transaction {
+        if result.is_err() {
+            let _ = connection.execute("ROLLBACK", []);
+        } else if let Err(e) = connection.execute("COMMIT", []) {
+            let _ = connection.execute("ROLLBACK", []);
+            return Err(e.into());
+        }
+    }
+    result
+}
+
+/// Reports whether DuckDB has a transaction open on a connection, however
+/// it was started.
+///
+/// Outside a transaction every statement runs in one of its own, so two
+/// statements only see the same transaction id inside one.
+fn transaction_open(connection: &DuckDBConnection) -> Result<bool, DuckyError> {
+    let transaction_id = || {
+        connection.query_row("SELECT current_transaction_id()", [], |row| {
+            row.get::<_, Option<u64>>(0)
+        })
+    };
+    Ok(transaction_id()? == transaction_id()?)
+}
+
+/// Runs each statement with its parameters in turn, returning the total
+/// number of rows changed.
+///
+/// Each distinct statement is prepared once. They differ only where
+/// parameters cast their placeholders to different types from one
+/// parameter set to the next.
+fn execute_each(
+    connection: &DuckDBConnection,
+    executions: &[(Cow<'_, str>, Vec<Value>)],
+) -> Result<usize, DuckyError> {
+    use std::collections::hash_map::Entry;
+
+    let mut statements = HashMap::new();
+    let mut changed = 0;
+    for (sql, params) in executions {
+        let sql: &str = sql;
+        let stmt = match statements.entry(sql) {
+            Entry::Occupied(entry) => entry.into_mut(),
+            Entry::Vacant(entry) => entry.insert(
+                connection
+                    .prepare(sql)
+                    .map_err(|e| DuckyError::from(e).in_statement(sql))?,
+            ),
+        };
+        changed += stmt
+            .execute(param_refs(params).as_slice())
+            .map_err(|e| DuckyError::from(e).in_statement(sql))?;
+    }
+    Ok(changed)
+}
+
+/// Runs a script of semicolon-separated SQL statements.
+///
+/// Statements run in order, each in its own implicit transaction unless
+/// the script opens one, and execution stops at the first failure.
+/// Statements that ran before the failure stay applied. Results of
+/// queries in the script are discarded.
+///
+/// Runs on a dirty CPU scheduler to avoid blocking the BEAM VM.
+///
+/// ## Arguments
+/// - `env`: NIF environment for term creation
+/// - `conn`: Connection resource
+/// - `sql_script`: SQL statements separated by semicolons
+///
+/// ## Returns
+/// - `{ok, nil}` if every statement succeeds
+/// - `{error, {index, reason}}` naming the 1-based index of the failing
+///   statement, with a reason that carries its SQL
+/// - `{error, reason}` if the connection can't be used
+#[rustler::nif(schedule = "DirtyCpu")]
+fn execute_batch<'a>(
+    env: Env<'a>,
+    conn: ResourceArc<ConnectionResource>,
+    sql_script: String,
+) -> NifResult<Term<'a>> {
+    let connection = match conn.lock() {
+        Ok(connection) => connection,
+        Err(e) => return Ok(e.encode(env)),
+    };
+
+    for (index, statement) in split_statements(&sql_script).into_iter().enumerate() {
+        if let Err(e) = connection.execute_batch(statement) {
+            let e = DuckyError::from(e).in_statement(statement.trim());
+            return Ok((atoms::error(), (index + 1, e.reason(env))).encode(env));
+        }
+    }
+    Ok((atoms::ok(), atoms::nil()).encode(env))
+}
+
+/// Runs a script of semicolon-separated SQL statements and returns the
+/// result of every statement that produces one.
+///
+/// Runs on a dirty CPU scheduler to avoid blocking the BEAM VM.
+///
+/// Statements run in order, each in its own implicit transaction unless
+/// the script opens one. Statements without a result, such as DDL, are
+/// left out of the results. Execution stops at the first failure.
+///
+/// ## Arguments
+/// - `env`: NIF environment for term creation
+/// - `conn`: Connection resource
+/// - `sql_script`: SQL statements separated by semicolons
+///
+/// ## Returns
+/// - `{ok, [{columns, rows}]}` with results in statement order
+/// - `{error, {results, reason}}` with the results of the statements that
+///   ran before the failing one
+/// - `{error, reason}` if the connection is closed
+#[rustler::nif(schedule = "DirtyCpu")]
+fn execute_multi<'a>(
+    env: Env<'a>,
+    conn: ResourceArc<ConnectionResource>,
+    sql_script: String,
+) -> NifResult<Term<'a>> {
+    let connection = match conn.lock() {
+        Ok(connection) => connection,
+        Err(e) => return Ok(e.encode(env)),
+    };
+    let _caller = CallerMonitor::new(env, &conn);
+
+    let mut results = Vec::new();
+    for statement in split_statements(&sql_script) {
+        match statement_result(env, &connection, statement) {
+            Ok(Some(result)) => results.push(result),
+            Ok(None) => {}
+            Err(e) => return Ok((atoms::error(), (results, e.reason(env))).encode(env)),
+        }
+    }
+    Ok((atoms::ok(), results).encode(env))
+}
+
+/// Runs one statement of a script, returning its result if it has columns.
+///
+/// Column names come from the statement rather than the rows, so queries
+/// that match no rows still report their columns.
+fn statement_result<'a>(
+    env: Env<'a>,
+    connection: &DuckDBConnection,
+    sql: &str,
+) -> Result<Option<(Vec<String>, Vec<Vec<Term<'a>>>)>, DuckyError> {
+    let mut stmt = connection
+        .prepare(sql)
+        .map_err(|e| DuckyError::from(e).in_statement(sql))?;
+    let (_, rows) = run_statement(env, &mut stmt, &[]).map_err(|e| e.in_statement(sql))?;
+
+    if stmt.column_count() == 0 {
+        return Ok(None);
+    }
+    Ok(Some((stmt.column_names(), rows)))
+}
+
+/// Splits a SQL script on semicolons outside of quotes, dollar-quoted
+/// strings and comments, dropping statements that contain only whitespace
+/// or comments.
+fn split_statements(script: &str) -> Vec<&str> {
+    let bytes = script.as_bytes();
+    let mut statements = Vec::new();
+    let mut start = 0;
+    let mut has_code = false;
+    let mut i = 0;
+
+    while i < bytes.len() {
+        if let Some(end) = quoted_end(bytes, i) {
+            // Comments alone don't make a statement
+            has_code |= matches!(bytes[i], b'\'' | b'"' | b'$');
+            i = end;
+            continue;
+        }
+        match bytes[i] {
+            b';' => {
+                if has_code {
+                    statements.push(&script[start..i]);
+                }
+                start = i + 1;
+                has_code = false;
+            }
+            c if !c.is_ascii_whitespace() => has_code = true,
+            _ => {}
+        }
+        i += 1;
+    }
+    if has_code {
+        statements.push(&script[start..]);
+    }
+
+    statements
+}
+
+/// Returns the index just past the quoted string, dollar-quoted string or
+/// comment starting at `bytes[i]`, or `None` if none starts there.
+///
+/// One left unterminated runs to the end of `bytes`.
+fn quoted_end(bytes: &[u8], i: usize) -> Option<usize> {
+    let rest = &bytes[i..];
+    let end = match rest[0] {
+        // A doubled quote inside a literal closes and reopens it, which
+        // ends up in the same place
+        quote @ (b'\'' | b'"') => rest[1..]
+            .iter()
+            .position(|&c| c == quote)
+            .map(|close| close + 2),
+        b'$' => {
+            // `$` is part of an identifier such as `a$b`, not an opener
+            if i > 0 && is_word_byte(bytes[i - 1]) {
+                return None;
+            }
+            let tag = dollar_quote_tag(rest)?;
+            rest[tag.len()..]
+                .windows(tag.len())
+                .position(|window| window == tag)
+                .map(|close| 2 * tag.len() + close)
+        }
+        b'-' if rest.get(1) == Some(&b'-') => rest.iter().position(|&c| c == b'\n'),
+        b'/' if rest.get(1) == Some(&b'*') => rest[2..]
+            .windows(2)
+            .position(|window| window == b"*/")
+            .map(|close| close + 4),
+        _ => return None,
+    };
+    Some(end.map_or(bytes.len(), |end| i + end))
+}
+
+/// Returns the `$$` or `$tag$` opening a dollar-quoted string at the start
+/// of `rest`, which starts with `$`.
+///
+/// Parameters such as `$1` and `$name` aren't followed by a closing `$`,
+/// and tags can't start with a digit, so neither is taken for a quote.
+fn dollar_quote_tag(rest: &[u8]) -> Option<&[u8]> {
+    let tag_len = rest[1..].iter().take_while(|&&c| is_word_byte(c)).count();
+    if rest.get(1).is_some_and(u8::is_ascii_digit) || rest.get(1 + tag_len) != Some(&b'$') {
+        return None;
+    }
+    Some(&rest[..tag_len + 2])
+}
+
+/// Whether a byte can appear in an unquoted identifier.
+fn is_word_byte(c: u8) -> bool {
+    c == b'_' || c.is_ascii_alphanumeric()
+}
+
+/// Wraps each placeholder in `sql` that has an entry in `casts` in a
+/// `CAST` to the type given there.
+///
+/// Placeholders are keyed by their 1-based position for `?`, by their
+/// number for `?N` and `$N`, and by their name for `$name`, skipping any
+/// inside quotes, dollar-quoted strings and comments.
+fn cast_placeholders<'s>(sql: &'s str, casts: &HashMap<String, impl AsRef<str>>) -> Cow<'s, str> {
+    if casts.is_empty() {
+        return Cow::Borrowed(sql);
+    }
+
+    let bytes = sql.as_bytes();
+    let mut cast = String::with_capacity(sql.len());
+    let mut copied = 0;
+    let mut position = 0;
+    let mut i = 0;
+
+    while i < bytes.len() {
+        if let Some(end) = quoted_end(bytes, i) {
+            i = end;
+            continue;
+        }
+        let start = i;
+        let suffix = match bytes[i] {
+            b'?' => bytes[i + 1..]
+                .iter()
+                .take_while(|c| c.is_ascii_digit())
+                .count(),
+            b'$' if i == 0 || !is_word_byte(bytes[i - 1]) => bytes[i + 1..]
+                .iter()
+                .take_while(|&&c| is_word_byte(c))
+                .count(),
+            _ => {
+                i += 1;
+                continue;
+            }
+        };
+        i += 1 + suffix;
+
+        let key = if bytes[start] == b'?' && suffix == 0 {
+            position += 1;
+            position.to_string()
+        } else {
+            sql[start + 1..i].to_string()
+        };
+        if let Some(type_name) = casts.get(&key) {
+            cast.push_str(&sql[copied..start]);
+            cast.push_str(&format!(
+                "CAST({} AS {})",
+                &sql[start..i],
+                type_name.as_ref()
+            ));
+            copied = i;
+        }
+    }
+    cast.push_str(&sql[copied..]);
+
+    Cow::Owned(cast)
+}
+
+/// Checks that a SQL statement parses and binds, without running it.
+///
+/// Runs on a dirty IO scheduler since it waits for any statement running on
+/// the connection.
+///
+/// Only a single statement is accepted, since preparing a script would run
+/// every statement but the last. Binding resolves table and column names,
+/// so references to missing tables are reported too.
+///
+/// ## Arguments
+/// - `conn`: Connection resource
+/// - `sql`: SQL statement to validate
+///
+/// ## Returns
+/// - `Ok(parameter_count)` with the number of parameters to bind
+/// - `Err(QuerySyntaxError)` with DuckDB's message if the SQL is invalid
+#[rustler::nif(schedule = "DirtyIo")]
+fn validate_sql(conn: ResourceArc<ConnectionResource>, sql: String) -> Result<usize, DuckyError> {
+    if split_statements(&sql).len() > 1 {
+        return Err(DuckyError::QuerySyntaxError(
+            "Expected a single statement".to_string(),
+        ));
+    }
+
+    let connection = conn.lock()?;
+    let stmt = connection
+        .prepare(&sql)
+        .map_err(|e| DuckyError::QuerySyntaxError(e.to_string()))?;
+    Ok(stmt.parameter_count())
+}
+
+/// Describes the columns a query would return, without running it.
+///
+/// Runs on a dirty IO scheduler since it waits for any statement running on
+/// the connection.
+///
+/// The query is wrapped in `SELECT * FROM (...) LIMIT 0` and the schema is
+/// read from the empty result, so no rows are scanned. Parameters are bound
+/// as NULL for this. Statements that can't be used as a subquery, such as
+/// INSERT or CREATE, are reported as producing no result set.
+///
+/// DuckDB marks every result column nullable, so a column is only reported
+/// non-nullable when `not_null_columns` traces it to a `NOT NULL` column
+/// of the queried table.
+///
+/// ## Arguments
+/// - `conn`: Connection resource
+/// - `sql`: SQL statement to describe
+///
+/// ## Returns
+/// - `Ok({[{name, type_atom, nullable}], result_set})` for queries
+/// - `Ok({[], no_result})` for statements without a result set
+/// - `Err(QuerySyntaxError)` if the statement itself is invalid
+#[rustler::nif(schedule = "DirtyIo")]
+fn describe(
+    conn: ResourceArc<ConnectionResource>,
+    sql: String,
+) -> Result<(Vec<(String, rustler::Atom, bool)>, rustler::Atom), DuckyError> {
+    let statements = split_statements(&sql);
+    let [statement] = statements.as_slice() else {
+        return Err(DuckyError::QuerySyntaxError(
+            "Expected a single statement".to_string(),
+        ));
+    };
+
+    let connection = conn.lock()?;
+    connection
+        .prepare(statement)
+        .map_err(|e| DuckyError::QuerySyntaxError(e.to_string()))?;
+
+    let Ok(mut stmt) = connection.prepare(&format!("SELECT * FROM ({}) LIMIT 0", statement)) else {
+        return Ok((Vec::new(), atoms::no_result()));
+    };
+
+    let nulls = vec![Value::Null; stmt.parameter_count()];
+    let schema = stmt
+        .query_arrow(param_refs(&nulls).as_slice())?
+        .get_schema();
+    let not_null = not_null_columns(&connection, statement)
+        .filter(|not_null| not_null.len() == schema.fields().len())
+        .unwrap_or_default();
+    let columns = schema
+        .fields()
+        .iter()
+        .enumerate()
+        .map(|(i, field)| {
+            (
+                field.name().to_string(),
+                data_type_atom(field.data_type()),
+                !not_null.get(i).copied().unwrap_or(false),
+            )
+        })
+        .collect();
+    Ok((columns, atoms::result_set()))
+}
+
+/// Finds which result columns of a query are read straight from a
+/// `NOT NULL` column of a table.
+///
+/// The query is followed through DuckDB's own parse of it from
+/// `json_serialize_sql`. Only a `SELECT` over a single table is followed:
+/// its column references and plain `*` are matched to the table's columns
+/// and every other expression counts as nullable. Joins, subqueries in
+/// `FROM`, set operations, CTEs, grouping sets and `*` with modifiers give
+/// `None`, as do sources that aren't catalog tables, such as files.
+///
+/// ## Returns
+/// - `Some(not_null)` with one flag per select list column
+/// - `None` if the query can't be followed
+fn not_null_columns(connection: &DuckDBConnection, statement: &str) -> Option<Vec<bool>> {
+    // One row per select list entry, none if the query isn't a SELECT from a
+    // single table. kind is 'column' for column references, 'star' for a
+    // plain * and 'other' otherwise; qualifier is the table a column or *
+    // names, if any. The JSON arrows bind looser than = and AND, hence the
+    // parentheses.
+    let mut stmt = connection
+        .prepare(
+            "WITH parsed AS ( \
+                 SELECT json_serialize_sql(?::VARCHAR) -> '$.statements[0].node' AS node \
+             ), source AS ( \
+                 SELECT node, node -> 'from_table' AS from_table FROM parsed \
+                 WHERE (node ->> 'type') = 'SELECT_NODE' \
+                 AND (from_table ->> 'type') = 'BASE_TABLE' \
+                 AND json_array_length(from_table, '$.column_name_alias') = 0 \
+                 AND json_array_length(node, '$.cte_map.map') = 0 \
+                 AND json_array_length(node, '$.group_sets') <= 1 \
+             ), items AS ( \
+                 SELECT i, from_table, (node -> 'select_list' -> i) AS item \
+                 FROM source, range(json_array_length(node, '$.select_list')::BIGINT) AS r(i) \
+             ) \
+             SELECT from_table ->> 'catalog_name', from_table ->> 'schema_name', \
+                 from_table ->> 'table_name', from_table ->> 'alias', \
+                 CASE \
+                     WHEN (item ->> 'class') = 'COLUMN_REF' THEN 'column' \
+                     WHEN (item ->> 'class') = 'STAR' \
+                         AND json_array_length(item, '$.exclude_list') = 0 \
+                         AND json_array_length(item, '$.qualified_exclude_list') = 0 \
+                         AND json_array_length(item, '$.replace_list') = 0 \
+                         AND json_array_length(item, '$.rename_list') = 0 \
+                         AND NOT (item ->> 'columns')::BOOLEAN \
+                         AND (item ->> 'expr') IS NULL THEN 'star' \
+                     ELSE 'other' \
+                 END, \
+                 json_extract_string(item, '$.column_names[*]')[-1], \
+                 CASE (item ->> 'class') \
+                     WHEN 'STAR' THEN (item ->> 'relation_name') \
+                     ELSE json_extract_string(item, '$.column_names[*]')[-2] \
+                 END \
+             FROM items ORDER BY i",
+        )
+        .ok()?;
+    let mut rows = stmt.query([statement]).ok()?;
+
+    let mut table = None;
+    let mut items = Vec::new();
+    while let Some(row) = rows.next().ok()? {
+        if table.is_none() {
+            let parts: [String; 4] = [
+                row.get(0).ok()?,
+                row.get(1).ok()?,
+                row.get(2).ok()?,
+                row.get(3).ok()?,
+            ];
+            table = Some(parts);
+        }
+        let kind: String = row.get(4).ok()?;
+        let name: Option<String> = row.get(5).ok()?;
+        let qualifier: Option<String> = row.get(6).ok()?;
+        items.push((kind, name, qualifier));
+    }
+    let [catalog, schema, table_name, alias] = table?;
+
+    // Resolved the way the query resolves it, temporary tables and the
+    // search path included
+    let qualified_name = [catalog, schema, table_name.clone()]
+        .iter()
+        .filter(|part| !part.is_empty())
+        .map(|part| quote_identifier(part))
+        .collect::<Vec<_>>()
+        .join(".");
+    let mut info = connection
+        .prepare("SELECT name, \"notnull\" FROM pragma_table_info(?) ORDER BY cid")
+        .ok()?;
+    let table_columns = info
+        .query_map([qualified_name], |row| Ok((row.get(0)?, row.get(1)?)))
+        .ok()?
+        .collect::<Result<Vec<(String, bool)>, _>>()
+        .ok()?;
+
+    let reference = if alias.is_empty() { table_name } else { alias };
+    let names_table = |qualifier: &Option<String>| {
+        qualifier.as_deref().is_none_or(|qualifier| {
+            qualifier.is_empty() || qualifier.eq_ignore_ascii_case(&reference)
+        })
+    };
+
+    let mut not_null = Vec::new();
+    for (kind, name, qualifier) in items {
+        match kind.as_str() {
+            "star" if names_table(&qualifier) => {
+                not_null.extend(table_columns.iter().map(|(_, not_null)| *not_null));
+            }
+            "star" => return None,
+            "column" if names_table(&qualifier) => not_null.push(
+                table_columns
+                    .iter()
+                    .find(|(column, _)| {
+                        name.as_deref()
+                            .is_some_and(|name| column.eq_ignore_ascii_case(name))
+                    })
+                    .is_some_and(|(_, not_null)| *not_null),
+            ),
+            _ => not_null.push(false),
+        }
+    }
+    Some(not_null)
+}
+
+/// Lists the columns of a table.
+///
+/// Runs on a dirty IO scheduler since it waits for any statement running on
+/// the connection.
+///
+/// ## Arguments
+/// - `env`: NIF environment for term creation
+/// - `conn`: Connection resource
+/// - `table_name`: Table name, optionally qualified as `schema.table`
+///
+/// ## Returns
+/// - `Ok([column_map])` in column order, each map holding `"name"`,
+///   `"type"`, `"nullable"`, `"default"` (`null` if none) and
+///   `"primary_key"`
+/// - `Err(DatabaseError)` if the table doesn't exist
+#[rustler::nif(schedule = "DirtyIo")]
+fn table_info<'a>(
+    env: Env<'a>,
+    conn: ResourceArc<ConnectionResource>,
+    table_name: String,
+) -> Result<Vec<Term<'a>>, DuckyError> {
+    use rustler::types::map::map_new;
+
+    let connection = conn.lock()?;
+
+    // Bound as a parameter, so the name needs no escaping
+    let mut stmt = connection.prepare(
+        "SELECT name, type, \"notnull\", dflt_value, pk FROM pragma_table_info(?) ORDER BY cid",
+    )?;
+    let mut rows = stmt.query(duckdb::params![table_name])?;
+
+    let map_error = |_| DuckyError::DatabaseError("Failed to build column map".to_string());
+    let mut columns = Vec::new();
+    while let Some(row) = rows.next()? {
+        let name: String = row.get(0)?;
+        let column_type: String = row.get(1)?;
+        let not_null: bool = row.get(2)?;
+        let default = match row.get::<_, Option<String>>(3)? {
+            Some(default) => default.encode(env),
+            None => atoms::null().encode(env),
+        };
+        let primary_key: bool = row.get(4)?;
+
+        let column = [
+            ("name", name.encode(env)),
+            ("type", column_type.encode(env)),
+            ("nullable", (!not_null).encode(env)),
+            ("default", default),
+            ("primary_key", primary_key.encode(env)),
+        ]
+        .into_iter()
+        .try_fold(map_new(env), |map, (key, value)| {
+            map.map_put(key.encode(env), value)
+        })
+        .map_err(map_error)?;
+        columns.push(column);
+    }
+
+    Ok(columns)
+}
+
+/// Lists the schemas of the connection's current database.
+///
+/// Runs on a dirty IO scheduler since it waits for any statement running on
+/// the connection.
+///
+/// DuckDB's system schemas, `information_schema` and `pg_catalog`, are
+/// left out.
+///
+/// ## Arguments
+/// - `conn`: Connection resource
+///
+/// ## Returns
+/// - `Ok(names)` sorted by name
+/// - `Err(DuckyError)` on failure
+#[rustler::nif(schedule = "DirtyIo")]
+fn list_schemas(conn: ResourceArc<ConnectionResource>) -> Result<Vec<String>, DuckyError> {
+    let connection = conn.lock()?;
+    let mut stmt = connection.prepare(
+        "SELECT schema_name FROM information_schema.schemata \
+         WHERE catalog_name = current_database() \
+         AND schema_name NOT IN ('information_schema', 'pg_catalog') \
+         ORDER BY schema_name",
+    )?;
+    let names = stmt
+        .query_map([], |row| row.get(0))?
+        .collect::<Result<Vec<String>, _>>()?;
+    Ok(names)
+}
+
+/// Lists the tables and views of the connection's current database.
+///
+/// Runs on a dirty IO scheduler since it waits for any statement running on
+/// the connection.
+///
+/// Temporary tables live in the separate `temp` database and system
+/// schemas are skipped, so only user tables are listed.
+///
+/// ## Arguments
+/// - `conn`: Connection resource
+/// - `schema`: Schema to list, or `nil` for every schema
+///
+/// ## Returns
+/// - `Ok(names)` sorted by schema, then name
+/// - `Err(DuckyError)` on failure
+#[rustler::nif(schedule = "DirtyIo")]
+fn list_tables(
+    conn: ResourceArc<ConnectionResource>,
+    schema: Option<String>,
+) -> Result<Vec<String>, DuckyError> {
+    let mut sql = "SELECT table_name FROM information_schema.tables \
+                   WHERE table_catalog = current_database() \
+                   AND table_schema NOT IN ('information_schema', 'pg_catalog')"
+        .to_string();
+    let mut params: Vec<Value> = Vec::new();
+    if let Some(schema) = schema {
+        sql.push_str(" AND table_schema = ?");
+        params.push(Value::Text(schema));
+    }
+    sql.push_str(" ORDER BY table_schema, table_name");
+
+    let connection = conn.lock()?;
+    let mut stmt = connection.prepare(&sql)?;
+    let names = stmt
+        .query_map(param_refs(&params).as_slice(), |row| row.get(0))?
+        .collect::<Result<Vec<String>, _>>()?;
+    Ok(names)
+}
+
+/// Checks whether a table or view exists in the connection's current
+/// database.
+///
+/// Runs on a dirty IO scheduler since it waits for any statement running on
+/// the connection.
+///
+/// ## Arguments
+/// - `conn`: Connection resource
+/// - `schema`: Schema to look in, or `nil` for the current schema, which
+///   is `main` unless changed with `set_schema`
+/// - `name`: Table name, matched exactly
+///
+/// ## Returns
+/// - `Ok(exists)` on success
+/// - `Err(DuckyError)` on failure
+#[rustler::nif(schedule = "DirtyIo")]
+fn table_exists(
+    conn: ResourceArc<ConnectionResource>,
+    schema: Option<String>,
+    name: String,
+) -> Result<bool, DuckyError> {
+    let connection = conn.lock()?;
+    Ok(connection.query_row(
+        "SELECT EXISTS (SELECT 1 FROM information_schema.tables \
+         WHERE table_catalog = current_database() \
+         AND table_schema = COALESCE(?::VARCHAR, current_schema()) \
+         AND table_name = ?)",
+        duckdb::params![schema, name],
+        |row| row.get(0),
+    )?)
+}
+
+/// Describes every schema of the connection's current database with its
+/// tables, views and their columns, in a single query.
+///
+/// The whole catalog is read and converted eagerly, so this is meant for
+/// browsing a database rather than for catalogs with many thousands of
+/// tables. System schemas are left out as in `list_schemas`.
+///
+/// ## Arguments
+/// - `env`: NIF environment for term creation
+/// - `conn`: Connection resource
+///
+/// ## Returns
+/// - `Ok([schema])` sorted by name, each a map with `"name"` and
+///   `"tables"`. Tables are maps with `"name"` and `"columns"`, and columns
+///   are maps with `"name"`, `"type"` and `"nullable"` in column order.
+/// - `Err(DuckyError)` on failure
+#[rustler::nif(schedule = "DirtyCpu")]
+fn describe_database<'a>(
+    env: Env<'a>,
+    conn: ResourceArc<ConnectionResource>,
+) -> Result<Vec<Term<'a>>, DuckyError> {
+    let connection = conn.lock()?;
+    let mut stmt = connection.prepare(
+        "SELECT s.schema_name, c.table_name, c.column_name, c.data_type, c.is_nullable \
+         FROM duckdb_schemas() s \
+         LEFT JOIN duckdb_columns() c \
+         ON c.database_name = s.database_name AND c.schema_name = s.schema_name \
+         WHERE s.database_name = current_database() \
+         AND s.schema_name NOT IN ('information_schema', 'pg_catalog') \
+         ORDER BY s.schema_name, c.table_name, c.column_index",
+    )?;
+    let mut rows = stmt.query([])?;
+
+    // Rows arrive sorted, so each schema and table is built up in turn
+    let mut schemas: Vec<(String, Vec<(String, Vec<Vec<Term<'a>>>)>)> = Vec::new();
+    while let Some(row) = rows.next()? {
+        let schema: String = row.get(0)?;
+        if schemas.last().is_none_or(|(name, _)| *name != schema) {
+            schemas.push((schema, Vec::new()));
+        }
+        let tables = &mut schemas.last_mut().expect("schema was just pushed").1;
+
+        // Schemas without tables come out of the join as a single NULL row
+        let Some(table) = row.get::<_, Option<String>>(1)? else {
+            continue;
+        };
+        if tables.last().is_none_or(|(name, _)| *name != table) {
+            tables.push((table, Vec::new()));
+        }
+        let columns = &mut tables.last_mut().expect("table was just pushed").1;
+
+        let name: String = row.get(2)?;
+        let column_type: String = row.get(3)?;
+        let nullable: bool = row.get(4)?;
+        columns.push(vec![
+            name.encode(env),
+            column_type.encode(env),
+            nullable.encode(env),
+        ]);
+    }
+
+    let column_keys = ["name", "type", "nullable"].map(String::from);
+    let table_keys = ["name", "columns"].map(String::from);
+    let schema_keys = ["name", "tables"].map(String::from);
+
+    let schema_rows = schemas
+        .into_iter()
+        .map(|(schema, tables)| {
+            let table_rows = tables
+                .into_iter()
+                .map(|(table, columns)| {
+                    let columns = rows_to_maps(env, &column_keys, columns)?;
+                    Ok(vec![table.encode(env), columns.encode(env)])
+                })
+                .collect::<Result<Vec<_>, DuckyError>>()?;
+            let tables = rows_to_maps(env, &table_keys, table_rows)?;
+            Ok(vec![schema.encode(env), tables.encode(env)])
+        })
+        .collect::<Result<Vec<_>, DuckyError>>()?;
+    rows_to_maps(env, &schema_keys, schema_rows)
+}
+
+/// Lists DuckDB's settings with their current values.
+///
+/// Runs on a dirty IO scheduler since it waits for any statement running on
+/// the connection.
+///
+/// ## Arguments
+/// - `env`: NIF environment for term creation
+/// - `conn`: Connection resource
+///
+/// ## Returns
+/// - `Ok([setting])` sorted by name, each a map with the keys `"name"`,
+///   `"value"`, `"description"`, `"input_type"` and `"scope"`
+/// - `Err(DuckyError)` on failure
+#[rustler::nif(schedule = "DirtyIo")]
+fn list_settings<'a>(
+    env: Env<'a>,
+    conn: ResourceArc<ConnectionResource>,
+) -> Result<Vec<Term<'a>>, DuckyError> {
+    let connection = conn.lock()?;
+    let (columns, rows) = execute_statement(
+        env,
+        &connection,
+        "SELECT name, value, description, input_type, scope \
+         FROM duckdb_settings() ORDER BY name",
+        &[],
+    )?;
+    rows_to_maps(env, &columns, rows)
+}
+
+/// Returns the query plan for a statement without running it.
+///
+/// Runs on a dirty CPU scheduler to avoid blocking the BEAM VM.
+///
+/// ## Arguments
+/// - `conn`: Connection resource
+/// - `sql`: SQL query string with optional `?` placeholders
+/// - `params_list`: Parameter values to bind, since plans can depend on them
+///
+/// ## Returns
+/// - `Ok(plan)` with the plan as newline-joined text
+/// - `Err(DuckyError)` on failure
+#[rustler::nif(schedule = "DirtyCpu")]
+fn explain(
+    conn: ResourceArc<ConnectionResource>,
+    sql: String,
+    params_list: Vec<Term>,
+) -> Result<String, DuckyError> {
+    let connection = conn.lock()?;
+
+    let (sql, params) = bind_params(&sql, params_list)?;
+    query_plan(&connection, "EXPLAIN", &sql, &params)
+}
+
+/// Runs a statement and returns its plan annotated with runtime statistics.
+///
+/// Runs on a dirty CPU scheduler to avoid blocking the BEAM VM.
+///
+/// ## Arguments
+/// - `conn`: Connection resource
+/// - `sql`: SQL query string with optional `?` placeholders
+/// - `params_list`: Parameter values to bind
+///
+/// ## Returns
+/// - `Ok(plan)` with the profiled plan as newline-joined text
+/// - `Err(DuckyError)` on failure
+#[rustler::nif(schedule = "DirtyCpu")]
+fn explain_analyze(
+    conn: ResourceArc<ConnectionResource>,
+    sql: String,
+    params_list: Vec<Term>,
+) -> Result<String, DuckyError> {
+    let connection = conn.lock()?;
+
+    let (sql, params) = bind_params(&sql, params_list)?;
+    query_plan(&connection, "EXPLAIN ANALYZE", &sql, &params)
+}
+
+/// Runs an `EXPLAIN` variant and joins the plan text of every result row.
+fn query_plan(
+    connection: &DuckDBConnection,
+    prefix: &str,
+    sql: &str,
+    params: &[Value],
+) -> Result<String, DuckyError> {
+    let mut stmt = connection.prepare(&format!("{} {}", prefix, sql))?;
+    let mut rows = stmt.query(param_refs(params).as_slice())?;
+
+    // Plans come back as (explain_key, explain_value) rows
+    let mut lines = Vec::new();
+    while let Some(row) = rows.next()? {
+        lines.push(row.get::<_, String>(1)?);
+    }
+    Ok(lines.join("\n"))
+}
+
+/// Returns the value most recently generated by a sequence in this session.
+///
+/// Runs on a dirty IO scheduler since it waits for any statement running on
+/// the connection.
+///
+/// ## Arguments
+/// - `conn`: Connection resource
+/// - `sequence_name`: Name of the sequence
+///
+/// ## Returns
+/// - `Ok(value)` on success
+/// - `Err(DatabaseError)` if `nextval` hasn't been called for the sequence
+///   on this connection yet, or the sequence doesn't exist
+#[rustler::nif(schedule = "DirtyIo")]
+fn currval(
+    conn: ResourceArc<ConnectionResource>,
+    sequence_name: String,
+) -> Result<i64, DuckyError> {
+    let connection = conn.lock()?;
+
+    sequence_value(&connection, "currval", &sequence_name).map_err(|e| match e {
+        DuckyError::DatabaseError(msg) if msg.contains("not yet defined") => {
+            DuckyError::DatabaseError(format!(
+                "currval called before nextval for sequence {} in this session",
+                sequence_name
+            ))
+        }
+        other => other,
+    })
+}
+
+/// Advances a sequence and returns the new value.
+///
+/// Runs on a dirty IO scheduler since it waits for any statement running on
+/// the connection.
+///
+/// ## Arguments
+/// - `conn`: Connection resource
+/// - `sequence_name`: Name of the sequence
+///
+/// ## Returns
+/// - `Ok(value)` on success
+/// - `Err(DatabaseError)` if the sequence doesn't exist
+#[rustler::nif(schedule = "DirtyIo")]
+fn nextval(
+    conn: ResourceArc<ConnectionResource>,
+    sequence_name: String,
+) -> Result<i64, DuckyError> {
+    let connection = conn.lock()?;
+
+    sequence_value(&connection, "nextval", &sequence_name)
+}
+
+/// Calls a sequence function with the sequence name bound as a parameter.
+fn sequence_value(
+    connection: &DuckDBConnection,
+    function: &str,
+    sequence_name: &str,
+) -> Result<i64, DuckyError> {
+    let sql = format!("SELECT {}(?)", function);
+    Ok(connection.query_row(&sql, duckdb::params![sequence_name], |row| row.get(0))?)
+}
+
+/// Loads a list of maps into a temporary table that queries can use.
+///
+/// The rows are converted to an Arrow record batch and copied into a
+/// temporary table through DuckDB's `arrow` table function. The table is
+/// replaced if it already exists and dropped when the connection closes.
+///
+/// Columns are the keys of the first row, in sorted order. Each column's
+/// type comes from its first non-nil value: integers become BIGINT,
+/// floats DOUBLE, strings VARCHAR, other binaries BLOB and booleans
+/// BOOLEAN. A column that is nil in every row gets DuckDB's NULL type.
+/// Integers in a column that also holds floats are promoted to DOUBLE, as
+/// in list parameters. Any other value of a different type fails the whole
+/// call, and a key missing from a later row is treated as nil.
+///
+/// ## Arguments
+/// - `conn`: Connection resource
+/// - `name`: Name of the table to create
+/// - `rows`: Maps from column name to value
+///
+/// ## Returns
+/// - `Ok(nil)` on success
+/// - `Err(QuerySyntaxError)` if the name is invalid, there are no rows, a
+///   row has an unknown key or a value doesn't match its column type
+/// - `Err(DatabaseError)` if the table can't be created
+#[rustler::nif(schedule = "DirtyCpu")]
+fn register_table(
+    conn: ResourceArc<ConnectionResource>,
+    name: String,
+    rows: Vec<HashMap<String, Term>>,
+) -> Result<rustler::Atom, DuckyError> {
+    use duckdb::vtab::arrow::{ArrowVTab, arrow_recordbatch_to_query_params};
+
+    if !is_plain_identifier(&name) {
+        return Err(DuckyError::QuerySyntaxError(format!(
+            "Invalid table name: {}",
+            name
+        )));
+    }
+
+    let Some(first) = rows.first() else {
+        return Err(DuckyError::QuerySyntaxError(
+            "Cannot infer a schema from an empty list of rows".to_string(),
+        ));
+    };
+    let mut columns: Vec<&String> = first.keys().collect();
+    columns.sort();
+
+    let mut values: Vec<Vec<Value>> = columns
+        .iter()
+        .map(|_| Vec::with_capacity(rows.len()))
+        .collect();
+    for row in &rows {
+        if let Some(key) = row.keys().find(|key| !first.contains_key(*key)) {
+            return Err(DuckyError::QuerySyntaxError(format!(
+                "Unknown column in row: {}",
+                key
+            )));
+        }
+        for (column, column_values) in columns.iter().zip(values.iter_mut()) {
+            column_values.push(match row.get(*column) {
+                Some(term) => term_to_duckdb_param(*term)?,
+                None => Value::Null,
+            });
+        }
+    }
+
+    let values: Vec<Vec<Value>> = values.into_iter().map(promote_numbers).collect();
+    let arrays = columns
+        .iter()
+        .zip(&values)
+        .map(|(column, column_values)| {
+            Ok((column.as_str(), values_to_array(column, column_values)?))
+        })
+        .collect::<Result<Vec<_>, DuckyError>>()?;
+    let batch = RecordBatch::try_from_iter(arrays)
+        .map_err(|e| DuckyError::DatabaseError(format!("Failed to build record batch: {}", e)))?;
+
+    let connection = conn.lock()?;
+
+    // Registering fails once the function exists, which is harmless
+    let _ = connection.register_table_function::<ArrowVTab>("arrow");
+    connection.execute(
+        &format!(
+            "CREATE OR REPLACE TEMP TABLE {} AS SELECT * FROM arrow(?, ?)",
+            name
+        ),
+        arrow_recordbatch_to_query_params(batch),
+    )?;
+    Ok(atoms::nil())
+}
+
+/// Builds an Arrow array for one column, typed by its first non-null value.
+fn values_to_array(
+    column: &str,
+    values: &[Value],
+) -> Result<duckdb::arrow::array::ArrayRef, DuckyError> {
+    use duckdb::arrow::array::{
+        BinaryArray, BooleanArray, Float64Array, Int64Array, NullArray, StringArray,
+    };
+
+    fn collect<'v, T, A: FromIterator<Option<T>>>(
+        column: &str,
+        values: &'v [Value],
+        extract: impl Fn(&'v Value) -> Option<T>,
+    ) -> Result<A, DuckyError> {
+        values
+            .iter()
+            .map(|value| match value {
+                Value::Null => Ok(None),
+                value => extract(value).map(Some).ok_or_else(|| {
+                    DuckyError::QuerySyntaxError(format!("Mixed value types in column {}", column))
+                }),
+            })
+            .collect()
+    }
+
+    let array: duckdb::arrow::array::ArrayRef = match values
+        .iter()
+        .find(|value| !matches!(value, Value::Null))
+    {
+        None => Arc::new(NullArray::new(values.len())),
+        Some(Value::Boolean(_)) => {
+            Arc::new(collect::<_, BooleanArray>(column, values, |v| match v {
+                Value::Boolean(b) => Some(*b),
+                _ => None,
+            })?)
+        }
+        Some(Value::BigInt(_)) => {
+            Arc::new(collect::<_, Int64Array>(column, values, |v| match v {
+                Value::BigInt(i) => Some(*i),
+                _ => None,
+            })?)
+        }
+        Some(Value::Double(_)) => {
+            Arc::new(collect::<_, Float64Array>(column, values, |v| match v {
+                Value::Double(f) => Some(*f),
+                _ => None,
+            })?)
+        }
+        Some(Value::Text(_)) => Arc::new(collect::<_, StringArray>(column, values, |v| match v {
+            Value::Text(s) => Some(s.as_str()),
+            _ => None,
+        })?),
+        Some(Value::Blob(_)) => Arc::new(collect::<_, BinaryArray>(column, values, |v| match v {
+            Value::Blob(b) => Some(b.as_slice()),
+            _ => None,
+        })?),
+        Some(_) => {
+            return Err(DuckyError::QuerySyntaxError(format!(
+                "Unsupported value type in column {}",
+                column
+            )));
+        }
+    };
+    Ok(array)
+}
+
+/// Imports a Parquet file into a table.
+///
+/// Runs on a dirty IO scheduler since the whole file is read.
+///
+/// ## Arguments
+/// - `conn`: Connection resource
+/// - `table_name`: Table to create or insert into
+/// - `file_path`: Path to the Parquet file, may contain globs
+/// - `options`: Import options
+///   - `"mode"`: `"create"` (default) to create the table from the file,
+///     or `"insert"` to append to an existing table
+///
+/// ## Returns
+/// - `Ok(rows_imported)` on success
+/// - `Err(QuerySyntaxError)` if the table name or an option is invalid
+/// - `Err(DatabaseError)` if the file can't be read or the insert fails
+#[rustler::nif(schedule = "DirtyIo")]
+fn import_parquet(
+    conn: ResourceArc<ConnectionResource>,
+    table_name: String,
+    file_path: String,
+    options: HashMap<String, Term>,
+) -> Result<usize, DuckyError> {
+    let mut mode = ImportMode::Create;
+    for (key, value) in sorted_options(&options) {
+        match key.as_str() {
+            "mode" => mode = import_mode(value)?,
+            _ => {
+                return Err(DuckyError::QuerySyntaxError(format!(
+                    "Unknown import option: {}",
+                    key
+                )));
+            }
+        }
+    }
+
+    let source = format!("read_parquet({})", quote_literal(&file_path));
+    let connection = conn.lock()?;
+    import_into(&connection, &table_name, &source, mode)
+}
+
+/// Imports a CSV file into a table.
+///
+/// Uses `read_csv_auto` so DuckDB detects the dialect and column types,
+/// or `read_csv` when the column types are given explicitly.
+///
+/// Runs on a dirty IO scheduler since the whole file is read.
+///
+/// ## Arguments
+/// - `conn`: Connection resource
+/// - `table_name`: Table to create or insert into
+/// - `path`: Path to the CSV file, may contain globs
+/// - `options`: Import options
+///   - `"mode"`: `"create"` (default) or `"insert"`
+///   - `"header"`: Whether the first line holds column names
+///   - `"delim"`: Single character separating columns
+///   - `"quote"`: Single character quoting values
+///   - `"nullstr"`: String representing NULL
+///   - `"columns"`: Column names mapped to DuckDB types. Maps don't keep
+///     insertion order, so pass a list of `{name, type}` tuples when the
+///     file's column order differs from the sorted names.
+///
+/// ## Returns
+/// - `Ok(rows_imported)` on success
+/// - `Err(QuerySyntaxError)` if the table name or an option is invalid
+/// - `Err(DatabaseError)` if the file can't be read or the insert fails
+#[rustler::nif(schedule = "DirtyIo")]
+fn import_csv(
+    conn: ResourceArc<ConnectionResource>,
+    table_name: String,
+    path: String,
+    options: HashMap<String, Term>,
+) -> Result<usize, DuckyError> {
+    let invalid = |key: &str| DuckyError::QuerySyntaxError(format!("Invalid value for {}", key));
+
+    let mut mode = ImportMode::Create;
+    let mut function = "read_csv_auto";
+    let mut csv_options = vec![quote_literal(&path)];
+    for (key, value) in sorted_options(&options) {
+        match key.as_str() {
+            "mode" => mode = import_mode(value)?,
+            "header" => {
+                let header = value.decode::<bool>().map_err(|_| invalid(key))?;
+                csv_options.push(format!("header = {}", header));
+            }
+            "delim" | "quote" => {
+                let character = value.decode::<String>().map_err(|_| invalid(key))?;
+                if character.chars().count() != 1 {
+                    return Err(DuckyError::QuerySyntaxError(format!(
+                        "{} must be a single character",
+                        key
+                    )));
+                }
+                csv_options.push(format!("{} = {}", key, quote_literal(&character)));
+            }
+            "nullstr" => {
+                let nullstr = value.decode::<String>().map_err(|_| invalid(key))?;
+                csv_options.push(format!("nullstr = {}", quote_literal(&nullstr)));
+            }
+            "columns" => {
+                let columns = match value.decode::<Vec<(String, String)>>() {
+                    Ok(columns) => columns,
+                    Err(_) => {
+                        let mut columns: Vec<(String, String)> = value
+                            .decode::<HashMap<String, String>>()
+                            .map_err(|_| invalid(key))?
+                            .into_iter()
+                            .collect();
+                        columns.sort();
+                        columns
+                    }
+                };
+                let fields: Vec<String> = columns
+                    .iter()
+                    .map(|(name, column_type)| {
+                        format!("{}: {}", quote_literal(name), quote_literal(column_type))
+                    })
+                    .collect();
+                csv_options.push(format!("columns = {{{}}}", fields.join(", ")));
+                function = "read_csv";
+            }
+            _ => {
+                return Err(DuckyError::QuerySyntaxError(format!(
+                    "Unknown import option: {}",
+                    key
+                )));
+            }
+        }
+    }
+
+    let source = format!("{}({})", function, csv_options.join(", "));
+    let connection = conn.lock()?;
+    import_into(&connection, &table_name, &source, mode)
+}
+
+/// Imports a JSON file into a table.
+///
+/// Uses `read_json_auto`, or `read_ndjson_auto` for newline-delimited
+/// records, so DuckDB detects the column types.
+///
+/// Runs on a dirty IO scheduler since the whole file is read.
+///
+/// ## Arguments
+/// - `conn`: Connection resource
+/// - `table_name`: Table to create or insert into
+/// - `path`: Path to the JSON file, may contain globs
+/// - `options`: Import options
+///   - `"mode"`: `"create"` (default) or `"insert"`
+///   - `"format"`: `"auto"` (default) to detect the layout, `"array"` for
+///     a top-level array of objects, or `"records"` for one object per line
+///   - `"maximum_object_size"`: Largest object in bytes, for files with
+///     objects over DuckDB's 16MB default
+///
+/// ## Returns
+/// - `Ok(rows_imported)` on success
+/// - `Err(QuerySyntaxError)` if the table name or an option is invalid
+/// - `Err(DatabaseError)` if the file can't be read or the insert fails
+#[rustler::nif(schedule = "DirtyIo")]
+fn import_json(
+    conn: ResourceArc<ConnectionResource>,
+    table_name: String,
+    path: String,
+    options: HashMap<String, Term>,
+) -> Result<usize, DuckyError> {
+    let invalid = |key: &str| DuckyError::QuerySyntaxError(format!("Invalid value for {}", key));
+
+    let mut mode = ImportMode::Create;
+    let mut function = "read_json_auto";
+    let mut json_options = vec![quote_literal(&path)];
+    for (key, value) in sorted_options(&options) {
+        match key.as_str() {
+            "mode" => mode = import_mode(value)?,
+            "format" => match value.decode::<String>().as_deref() {
+                Ok("auto") => {}
+                Ok("array") => json_options.push("format = 'array'".to_string()),
+                Ok("records") => function = "read_ndjson_auto",
+                _ => return Err(invalid(key)),
+            },
+            "maximum_object_size" => {
+                let size = value
+                    .decode::<u64>()
+                    .ok()
+                    .filter(|size| *size > 0)
+                    .ok_or_else(|| invalid(key))?;
+                json_options.push(format!("maximum_object_size = {}", size));
+            }
+            _ => {
+                return Err(DuckyError::QuerySyntaxError(format!(
+                    "Unknown import option: {}",
+                    key
+                )));
+            }
+        }
+    }
+
+    let source = format!("{}({})", function, json_options.join(", "));
+    let connection = conn.lock()?;
+    import_into(&connection, &table_name, &source, mode)
+}
+
+/// Copies in-memory data such as a CSV document into an existing table.
+///
+/// Runs on a dirty IO scheduler. DuckDB's COPY only reads from files, so
+/// the data is written to a temporary file that is removed afterwards,
+/// whether or not the copy succeeds.
+///
+/// ## Arguments
+/// - `conn`: Connection resource
+/// - `table_name`: Table to insert into
+/// - `format`: `"csv"`, `"json"` or `"parquet"`
+/// - `data`: Binary or iolist with the data in that format
+///
+/// ## Returns
+/// - `Ok(rows_copied)` on success
+/// - `Err(QuerySyntaxError)` if the table name or format is invalid
+/// - `Err(DatabaseError)` if the data can't be written or parsed
+#[rustler::nif(schedule = "DirtyIo")]
+fn copy_from_iodata(
+    conn: ResourceArc<ConnectionResource>,
+    table_name: String,
+    format: String,
+    data: Term,
+) -> Result<usize, DuckyError> {
+    if !is_plain_identifier(&table_name) {
+        return Err(DuckyError::QuerySyntaxError(format!(
+            "Invalid table name: {}",
+            table_name
+        )));
+    }
+    if !["csv", "json", "parquet"].contains(&format.as_str()) {
+        return Err(DuckyError::QuerySyntaxError(format!(
+            "Unsupported copy format: {}",
+            format
+        )));
+    }
+
+    let data = data
+        .decode::<rustler::Binary>()
+        .or_else(|_| rustler::Binary::from_iolist(data))
+        .map_err(|_| DuckyError::DatabaseError("Data must be a binary or iolist".to_string()))?;
+
+    let file = TempFile::create(&format, data.as_slice())?;
+    let sql = format!(
+        "COPY {} FROM {} (FORMAT {})",
+        table_name,
+        quote_literal(&file.path.to_string_lossy()),
+        format
+    );
+
+    let connection = conn.lock()?;
+    Ok(connection.execute(&sql, [])?)
+}
+
+/// Temporary file removed when dropped.
+struct TempFile {
+    path: std::path::PathBuf,
+}
+
+impl TempFile {
+    /// Writes data to a new file in the system temporary directory.
+    ///
+    /// The name has a random part and the file is created exclusively, so
+    /// a file or link planted at a guessed path is never written through.
+    fn create(extension: &str, data: &[u8]) -> Result<Self, DuckyError> {
+        use std::hash::{BuildHasher, RandomState};
+        use std::io::{ErrorKind, Write};
+
+        const ATTEMPTS: usize = 16;
+
+        let write_error = |e: std::io::Error| {
+            DuckyError::DatabaseError(format!("Failed to write temporary file: {}", e))
+        };
+
+        for _ in 0..ATTEMPTS {
+            // RandomState is keyed from the OS's random source, so the hash
+            // of a fixed value can't be guessed
+            let random = RandomState::new().hash_one(std::process::id());
+            let name = format!("ducky_{}_{:016x}.{}", std::process::id(), random, extension);
+            let path = std::env::temp_dir().join(name);
+
+            let mut handle = match std::fs::OpenOptions::new()
+                .write(true)
+                .create_new(true)
+                .open(&path)
+            {
+                Ok(handle) => handle,
+                Err(e) if e.kind() == ErrorKind::AlreadyExists => continue,
+                Err(e) => return Err(write_error(e)),
+            };
+
+            // Removes the file again if writing fails. The handle is closed
+            // first, since Windows can't remove an open file.
+            let file = TempFile { path };
+            let written = handle.write_all(data);
+            drop(handle);
+            written.map_err(write_error)?;
+            return Ok(file);
+        }
+
+        Err(DuckyError::DatabaseError(
+            "Failed to create temporary file: no unused name found".to_string(),
+        ))
+    }
+}
+
+impl Drop for TempFile {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_file(&self.path);
+    }
+}
+
+/// Writes the results of a query to a Parquet file.
+///
+/// Runs on a dirty IO scheduler since the whole result is written.
+///
+/// ## Arguments
+/// - `conn`: Connection resource
+/// - `sql`: SQL query string with optional `?` placeholders
+/// - `params_list`: Parameter values to bind into the query
+/// - `out_path`: Path of the file to write
+/// - `options`: Export options
+///   - `"compression"`: One of `"snappy"` (default), `"zstd"`, `"gzip"`,
+///     `"lz4"`, `"brotli"` or `"uncompressed"`
+///
+/// ## Returns
+/// - `Ok(nil)` on success
+/// - `Err(QuerySyntaxError)` if an option is invalid
+/// - `Err(DatabaseError)` if the query fails or the file can't be written
+#[rustler::nif(schedule = "DirtyIo")]
+fn export_parquet(
+    conn: ResourceArc<ConnectionResource>,
+    sql: String,
+    params_list: Vec<Term>,
+    out_path: String,
+    options: HashMap<String, Term>,
+) -> Result<rustler::Atom, DuckyError> {
+    let mut copy_options = vec!["FORMAT PARQUET".to_string()];
+    for (key, value) in sorted_options(&options) {
+        match key.as_str() {
+            "compression" => {
+                let codec = value.decode::<String>().ok().filter(|codec| {
+                    ["snappy", "zstd", "gzip", "lz4", "brotli", "uncompressed"]
+                        .contains(&codec.as_str())
+                });
+                let codec = codec.ok_or_else(|| {
+                    DuckyError::QuerySyntaxError("Invalid value for compression".to_string())
+                })?;
+                copy_options.push(format!("COMPRESSION {}", codec));
+            }
+            _ => {
+                return Err(DuckyError::QuerySyntaxError(format!(
+                    "Unknown export option: {}",
+                    key
+                )));
+            }
+        }
+    }
+
+    let (sql, params) = bind_params(&sql, params_list)?;
+    let copy = format!(
+        "COPY ({}) TO {} ({})",
+        sql,
+        quote_literal(&out_path),
+        copy_options.join(", ")
+    );
+
+    let connection = conn.lock()?;
+    let mut stmt = connection.prepare(&copy)?;
+    stmt.execute(param_refs(&params).as_slice())?;
+    Ok(atoms::nil())
+}
+
+/// Writes the results of a query to a CSV file.
+///
+/// Runs on a dirty IO scheduler since the whole result is written.
+///
+/// ## Arguments
+/// - `conn`: Connection resource
+/// - `sql`: SQL query string with optional `?` placeholders
+/// - `params_list`: Parameter values to bind into the query
+/// - `out_path`: Path of the file to write
+/// - `options`: Export options
+///   - `"header"`: Whether to write column names first, defaults to true
+///   - `"delim"`: Single character separating columns
+///   - `"quote"`: Single character quoting values
+///
+/// ## Returns
+/// - `Ok(nil)` on success
+/// - `Err(QuerySyntaxError)` if an option is invalid
+/// - `Err(DatabaseError)` if the output directory doesn't exist, the query
+///   fails or the file can't be written
+#[rustler::nif(schedule = "DirtyIo")]
+fn export_csv(
+    conn: ResourceArc<ConnectionResource>,
+    sql: String,
+    params_list: Vec<Term>,
+    out_path: String,
+    options: HashMap<String, Term>,
+) -> Result<rustler::Atom, DuckyError> {
+    let invalid = |key: &str| DuckyError::QuerySyntaxError(format!("Invalid value for {}", key));
+
+    let mut header = true;
+    let mut copy_options = vec!["FORMAT CSV".to_string()];
+    for (key, value) in sorted_options(&options) {
+        match key.as_str() {
+            "header" => header = value.decode::<bool>().map_err(|_| invalid(key))?,
+            "delim" | "quote" => {
+                let character = value.decode::<String>().map_err(|_| invalid(key))?;
+                if character.chars().count() != 1 {
+                    return Err(DuckyError::QuerySyntaxError(format!(
+                        "{} must be a single character",
+                        key
+                    )));
+                }
+                copy_options.push(format!(
+                    "{} {}",
+                    key.to_uppercase(),
+                    quote_literal(&character)
+                ));
+            }
+            _ => {
+                return Err(DuckyError::QuerySyntaxError(format!(
+                    "Unknown export option: {}",
+                    key
+                )));
+            }
+        }
+    }
+    copy_options.push(format!("HEADER {}", header));
+
+    // DuckDB's message for a missing directory only names the file
+    let dir = std::path::Path::new(&out_path).parent();
+    if let Some(dir) = dir.filter(|dir| !dir.as_os_str().is_empty() && !dir.is_dir()) {
+        return Err(DuckyError::DatabaseError(format!(
+            "Output directory doesn't exist: {}",
+            dir.display()
+        )));
+    }
+
+    let (sql, params) = bind_params(&sql, params_list)?;
+    let copy = format!(
+        "COPY ({}) TO {} ({})",
+        sql,
+        quote_literal(&out_path),
+        copy_options.join(", ")
+    );
+
+    let connection = conn.lock()?;
+    let mut stmt = connection.prepare(&copy)?;
+    stmt.execute(param_refs(&params).as_slice())?;
+    Ok(atoms::nil())
+}
+
+/// Whether an import creates its target table or appends to it.
+#[derive(Clone, Copy)]
+enum ImportMode {
+    Create,
+    Insert,
+}
+
+/// Decodes the `"mode"` import option.
+fn import_mode(value: Term) -> Result<ImportMode, DuckyError> {
+    match value.decode::<String>().as_deref() {
+        Ok("create") => Ok(ImportMode::Create),
+        Ok("insert") => Ok(ImportMode::Insert),
+        _ => Err(DuckyError::QuerySyntaxError(
+            "Invalid value for mode".to_string(),
+        )),
+    }
+}
+
+/// Returns options sorted by key so errors are reported consistently.
+fn sorted_options<'m, 'a>(options: &'m HashMap<String, Term<'a>>) -> Vec<(&'m String, Term<'a>)> {
+    let mut entries: Vec<_> = options.iter().map(|(key, value)| (key, *value)).collect();
+    entries.sort_by(|a, b| a.0.cmp(b.0));
+    entries
+}
+
+/// Creates or fills a table from a table function call such as
+/// `read_parquet('data.parquet')`, returning the number of rows written.
+fn import_into(
+    connection: &DuckDBConnection,
+    table_name: &str,
+    source: &str,
+    mode: ImportMode,
+) -> Result<usize, DuckyError> {
+    if !is_plain_identifier(table_name) {
+        return Err(DuckyError::QuerySyntaxError(format!(
+            "Invalid table name: {}",
+            table_name
+        )));
+    }
+
+    let sql = match mode {
+        ImportMode::Create => format!("CREATE TABLE {} AS SELECT * FROM {}", table_name, source),
+        ImportMode::Insert => format!("INSERT INTO {} SELECT * FROM {}", table_name, source),
+    };
+    Ok(connection.execute(&sql, [])?)
+}
+
+/// Quotes a string as a SQL literal, doubling any embedded single quotes.
+fn quote_literal(value: &str) -> String {
+    format!("'{}'", value.replace('\'', "''"))
+}
+
+/// Quotes a name as a SQL identifier, doubling any embedded double quotes.
+fn quote_identifier(name: &str) -> String {
+    format!("\"{}\"", name.replace('"', "\"\""))
+}
+
+/// Quotes a name as a DuckDB identifier so it can be spliced into SQL.
+///
+/// Any name is accepted, including ones with quotes, spaces or reserved
+/// words; embedded double quotes are doubled.
+///
+/// ## Arguments
+/// - `name`: Table, column or other identifier
+///
+/// ## Returns
+/// - The name wrapped in double quotes
+#[rustler::nif(name = "quote_identifier")]
+fn quote_identifier_nif(name: String) -> String {
+    quote_identifier(&name)
+}
+
+/// Executes a SQL query and returns its result column by column.
+///
+/// Runs on a dirty CPU scheduler to avoid blocking the BEAM VM.
+///
+/// Values are read straight from DuckDB's columnar record batches, so no
+/// per-row lists are built. Column names come from the result schema and
+/// are reported even when no rows match.
+///
+/// ## Arguments
+/// - `env`: NIF environment for term creation
+/// - `conn`: Connection resource
+/// - `sql`: SQL query string with optional `?` placeholders
+/// - `params_list`: Parameter values to bind
+///
+/// ## Returns
+/// - `Ok({columns, column_data})` where column_data holds one list of
+///   values per column, in the same order as columns
+/// - `Err(DuckyError)` on failure
+#[rustler::nif(schedule = "DirtyCpu")]
+fn execute_query_columnar<'a>(
+    env: Env<'a>,
+    conn: ResourceArc<ConnectionResource>,
+    sql: String,
+    params_list: Vec<Term<'a>>,
+) -> Result<(Vec<String>, Vec<Vec<Term<'a>>>), DuckyError> {
+    let connection = conn.lock()?;
+    let _caller = CallerMonitor::new(env, &conn);
+
+    let (sql, params) = bind_params(&sql, params_list)?;
+    let mut stmt = connection
+        .prepare(&sql)
+        .map_err(|e| DuckyError::from(e).in_statement(&sql))?;
+    let arrow = stmt
+        .query_arrow(param_refs(&params).as_slice())
+        .map_err(|e| DuckyError::from(e).in_statement(&sql))?;
+
+    let columns: Vec<String> = arrow
+        .get_schema()
+        .fields()
+        .iter()
+        .map(|field| field.name().to_string())
+        .collect();
+
+    let mut column_data: Vec<Vec<Term<'a>>> = vec![Vec::new(); columns.len()];
+    for batch in arrow {
+        for (values, array) in column_data.iter_mut().zip(batch.columns()) {
+            values.reserve(array.len());
+            for row_idx in 0..array.len() {
+                let term = if array.is_null(row_idx) {
+                    atoms::null().encode(env)
+                } else {
+                    element_to_term(env, array.as_ref(), row_idx).map_err(|_| {
+                        DuckyError::DatabaseError("Failed to convert value".to_string())
+                    })?
+                };
+                values.push(term);
+            }
+        }
+    }
+
+    Ok((columns, column_data))
+}
+
+/// Executes a SQL query and returns the result as an Arrow IPC stream.
+///
+/// Runs on a dirty CPU scheduler to avoid blocking the BEAM VM.
+///
+/// Record batches are serialized as they come out of DuckDB, skipping the
+/// per-value term conversion, so large analytical results can be handed
+/// to an Arrow reader such as Explorer cheaply.
+///
+/// ## Arguments
+/// - `env`: NIF environment for binary creation
+/// - `conn`: Connection resource
+/// - `sql`: SQL query string with optional `?` placeholders
+/// - `params_list`: Parameter values to bind
+///
+/// ## Returns
+/// - `Ok(binary)` holding the schema followed by every record batch
+/// - `Err(DuckyError)` on failure
+#[rustler::nif(schedule = "DirtyCpu")]
+fn query_arrow_ipc<'a>(
+    env: Env<'a>,
+    conn: ResourceArc<ConnectionResource>,
+    sql: String,
+    params_list: Vec<Term<'a>>,
+) -> Result<rustler::Binary<'a>, DuckyError> {
+    let connection = conn.lock()?;
+
+    let (sql, params) = bind_params(&sql, params_list)?;
+    let mut stmt = connection.prepare(&sql)?;
+    let arrow = stmt.query_arrow(param_refs(&params).as_slice())?;
+
+    let ipc_error = |e: duckdb::arrow::error::ArrowError| {
+        DuckyError::DatabaseError(format!("Failed to write Arrow IPC stream: {}", e))
+    };
+    let schema = arrow.get_schema();
+    let mut writer = StreamWriter::try_new(Vec::new(), &schema).map_err(ipc_error)?;
+    for batch in arrow {
+        writer.write(&batch).map_err(ipc_error)?;
+    }
+    let bytes = writer.into_inner().map_err(ipc_error)?;
+
+    let mut binary = rustler::OwnedBinary::new(bytes.len())
+        .ok_or_else(|| DuckyError::OutOfMemory("Failed to allocate binary".to_string()))?;
+    binary.as_mut_slice().copy_from_slice(&bytes);
+    Ok(binary.release(env))
+}
+
+/// Executes a SQL query and returns the result as a list of Arrow IPC
+/// streams of up to `batch_rows` rows each.
+///
+/// Runs on a dirty CPU scheduler to avoid blocking the BEAM VM.
+///
+/// DuckDB's record batches are re-chunked to the requested size. Every
+/// binary is a complete stream holding the schema and one record batch,
+/// so each can be decoded on its own as it's consumed.
+///
+/// ## Arguments
+/// - `env`: NIF environment for binary creation
+/// - `conn`: Connection resource
+/// - `sql`: SQL query string with optional `?` placeholders
+/// - `params_list`: Parameter values to bind
+/// - `batch_rows`: Maximum number of rows per batch
+///
+/// ## Returns
+/// - `Ok(binaries)`, empty when the query returns no rows
+/// - `Err(QuerySyntaxError)` if `batch_rows` is zero
+/// - `Err(DuckyError)` on other failures
+#[rustler::nif(schedule = "DirtyCpu")]
+fn query_arrow_batches<'a>(
+    env: Env<'a>,
+    conn: ResourceArc<ConnectionResource>,
+    sql: String,
+    params_list: Vec<Term<'a>>,
+    batch_rows: usize,
+) -> Result<Vec<Term<'a>>, DuckyError> {
+    if batch_rows == 0 {
+        return Err(DuckyError::QuerySyntaxError(
+            "Invalid batch size: 0".to_string(),
+        ));
+    }
+
+    let connection = conn.lock()?;
+
+    let (sql, params) = bind_params(&sql, params_list)?;
+    let mut stmt = connection.prepare(&sql)?;
+    let arrow = stmt.query_arrow(param_refs(&params).as_slice())?;
+    let schema = arrow.get_schema();
+
+    let mut streams = Vec::new();
+    let mut pending = Vec::new();
+    let mut pending_rows = 0;
+    for batch in arrow {
+        let mut offset = 0;
+        while offset < batch.num_rows() {
+            let take = (batch_rows - pending_rows).min(batch.num_rows() - offset);
+            pending.push(batch.slice(offset, take));
+            pending_rows += take;
+            offset += take;
+
+            if pending_rows == batch_rows {
+                streams.push(ipc_stream(env, &schema, &pending)?);
+                pending.clear();
+                pending_rows = 0;
+            }
+        }
+    }
+    if pending_rows > 0 {
+        streams.push(ipc_stream(env, &schema, &pending)?);
+    }
+
+    Ok(streams)
+}
+
+/// Serializes slices of a result as an Arrow IPC stream holding a single
+/// record batch.
+fn ipc_stream<'a>(
+    env: Env<'a>,
+    schema: &duckdb::arrow::datatypes::SchemaRef,
+    batches: &[RecordBatch],
+) -> Result<Term<'a>, DuckyError> {
+    let ipc_error = |e: duckdb::arrow::error::ArrowError| {
+        DuckyError::DatabaseError(format!("Failed to write Arrow IPC stream: {}", e))
+    };
+    let batch = duckdb::arrow::compute::concat_batches(schema, batches).map_err(ipc_error)?;
+    let mut writer = StreamWriter::try_new(Vec::new(), schema).map_err(ipc_error)?;
+    writer.write(&batch).map_err(ipc_error)?;
+    let bytes = writer.into_inner().map_err(ipc_error)?;
+    Ok(blob_to_term(env, &bytes))
+}
+
+/// Executes a SQL query and returns a hash of its result instead of the
+/// rows.
+///
+/// Runs on a dirty CPU scheduler to avoid blocking the BEAM VM.
+///
+/// The result is streamed batch by batch into a SHA-256 hash of the column
+/// names and of every value, each tagged with its kind and length, so
+/// equal results hash the same however DuckDB split them into batches and
+/// values can't run into each other. Integers hash by value whatever their
+/// width. The hash covers the row order, so queries should use `ORDER BY`
+/// when the order isn't otherwise fixed.
+///
+/// ## Arguments
+/// - `env`: NIF environment
+/// - `conn`: Connection resource
+/// - `sql`: SQL query string with optional `?` placeholders
+/// - `params_list`: Parameter values to bind
+///
+/// ## Returns
+/// - `Ok(hash)` as 64 lowercase hex digits
+/// - `Err(DatabaseError)` if the result holds a value of a type that can't
+///   be hashed
+/// - `Err(DuckyError)` on other failures
+#[rustler::nif(schedule = "DirtyCpu")]
+fn query_hash<'a>(
+    env: Env<'a>,
+    conn: ResourceArc<ConnectionResource>,
+    sql: String,
+    params_list: Vec<Term<'a>>,
+) -> Result<String, DuckyError> {
+    let connection = conn.lock()?;
+    let _caller = CallerMonitor::new(env, &conn);
+
+    let (sql, params) = bind_params(&sql, params_list)?;
+    with_statement(&connection, &sql, |stmt| {
+        let arrow = stmt.query_arrow(param_refs(&params).as_slice())?;
+        let mut hasher = ResultHasher::new(&arrow.get_schema());
+        for batch in arrow {
+            hasher.add_batch(&batch)?;
+        }
+        Ok(hasher.finish())
+    })
+}
+
+/// Hashes a query result one record batch at a time.
+struct ResultHasher {
+    sha: Sha256,
+}
+
+impl ResultHasher {
+    /// Starts a hash with the result's column names.
+    fn new(schema: &duckdb::arrow::datatypes::Schema) -> Self {
+        let mut hasher = Self { sha: Sha256::new() };
+        hasher.update_length(schema.fields().len());
+        for field in schema.fields() {
+            hasher.update_tagged(b'c', field.name().as_bytes());
+        }
+        hasher
+    }
+
+    /// Adds every row of a batch, in order.
+    fn add_batch(&mut self, batch: &RecordBatch) -> Result<(), DuckyError> {
+        for row in 0..batch.num_rows() {
+            for column in batch.columns() {
+                self.add_element(column.as_ref(), row)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Returns the hash as lowercase hex digits.
+    fn finish(self) -> String {
+        self.sha
+            .finalize()
+            .iter()
+            .map(|byte| format!("{:02x}", byte))
+            .collect()
+    }
+
+    /// Adds one element of an array, recursing into nested values.
+    fn add_element(&mut self, array: &dyn Array, index: usize) -> Result<(), DuckyError> {
+        use duckdb::arrow::array::AsArray;
+        use duckdb::arrow::datatypes::DataType;
+
+        if array.is_null(index) {
+            self.sha.update([b'0']);
+            return Ok(());
+        }
+
+        match array.data_type() {
+            DataType::List(_) => self.add_sequence(array.as_list::<i32>().value(index).as_ref()),
+            DataType::LargeList(_) => {
+                self.add_sequence(array.as_list::<i64>().value(index).as_ref())
+            }
+            DataType::FixedSizeList(_, _) => {
+                self.add_sequence(array.as_fixed_size_list().value(index).as_ref())
+            }
+            DataType::Struct(fields) => {
+                let struct_array = array.as_struct();
+                self.sha.update([b'{']);
+                self.update_length(fields.len());
+                for (field, column) in fields.iter().zip(struct_array.columns()) {
+                    self.update_tagged(b'k', field.name().as_bytes());
+                    self.add_element(column.as_ref(), index)?;
+                }
+                Ok(())
+            }
+            DataType::Map(_, _) => {
+                let entries = array.as_map().value(index);
+                self.sha.update([b'm']);
+                self.update_length(entries.len());
+                for entry in 0..entries.len() {
+                    self.add_element(entries.column(0).as_ref(), entry)?;
+                    self.add_element(entries.column(1).as_ref(), entry)?;
+                }
+                Ok(())
+            }
+            DataType::Union(fields, _) => {
+                let union = array.as_union();
+                let type_id = union.type_id(index);
+                let member = fields
+                    .iter()
+                    .find(|(id, _)| *id == type_id)
+                    .map_or("", |(_, field)| field.name().as_str());
+                self.update_tagged(b'u', member.as_bytes());
+                self.add_element(union.child(type_id).as_ref(), union.value_offset(index))
+            }
+            DataType::Dictionary(key_type, _) => {
+                use duckdb::arrow::datatypes::{UInt8Type, UInt16Type, UInt32Type};
+
+                // ENUM values hash like the strings they stand for
+                let key = match key_type.as_ref() {
+                    DataType::UInt8 => array.as_dictionary::<UInt8Type>().key(index),
+                    DataType::UInt16 => array.as_dictionary::<UInt16Type>().key(index),
+                    DataType::UInt32 => array.as_dictionary::<UInt32Type>().key(index),
+                    _ => None,
+                }
+                .ok_or_else(|| {
+                    DuckyError::DatabaseError(format!(
+                        "Can't hash values of type {}",
+                        array.data_type()
+                    ))
+                })?;
+                self.add_element(array.as_any_dictionary().values().as_ref(), key)
+            }
+            DataType::Decimal128(_, scale) => {
+                let unscaled = array
+                    .as_primitive::<duckdb::arrow::datatypes::Decimal128Type>()
+                    .value(index);
+                self.sha.update([b'n']);
+                self.sha.update(unscaled.to_le_bytes());
+                self.sha.update(scale.to_le_bytes());
+                Ok(())
+            }
+            _ => {
+                let value = arrow_element_to_value_ref(array, index).map_err(|_| {
+                    DuckyError::DatabaseError(format!(
+                        "Can't hash values of type {}",
+                        array.data_type()
+                    ))
+                })?;
+                self.add_scalar(value)
+            }
+        }
+    }
+
+    /// Adds the elements of a list or array.
+    fn add_sequence(&mut self, values: &dyn Array) -> Result<(), DuckyError> {
+        self.sha.update([b'[']);
+        self.update_length(values.len());
+        for index in 0..values.len() {
+            self.add_element(values, index)?;
+        }
+        Ok(())
+    }
+
+    /// Adds a non-nested, non-NULL value.
+    fn add_scalar(&mut self, value: ValueRef<'_>) -> Result<(), DuckyError> {
+        let integer = |value: i128| value.to_le_bytes().to_vec();
+        let (tag, bytes) = match value {
+            ValueRef::Boolean(b) => (b'?', vec![u8::from(b)]),
+            ValueRef::TinyInt(i) => (b'i', integer(i128::from(i))),
+            ValueRef::SmallInt(i) => (b'i', integer(i128::from(i))),
+            ValueRef::Int(i) => (b'i', integer(i128::from(i))),
+            ValueRef::BigInt(i) => (b'i', integer(i128::from(i))),
+            ValueRef::HugeInt(i) => (b'i', integer(i)),
+            ValueRef::UTinyInt(i) => (b'i', integer(i128::from(i))),
+            ValueRef::USmallInt(i) => (b'i', integer(i128::from(i))),
+            ValueRef::UInt(i) => (b'i', integer(i128::from(i))),
+            ValueRef::UBigInt(i) => (b'i', integer(i128::from(i))),
+            ValueRef::Float(f) => (b'f', f64::from(f).to_le_bytes().to_vec()),
+            ValueRef::Double(f) => (b'f', f.to_le_bytes().to_vec()),
+            ValueRef::Text(bytes) => (b's', bytes.to_vec()),
+            ValueRef::Blob(bytes) => (b'b', bytes.to_vec()),
+            ValueRef::Date32(days) => (b'd', days.to_le_bytes().to_vec()),
+            ValueRef::Timestamp(unit, value) => (
+                b't',
+                normalize_to_micros(unit, value).to_le_bytes().to_vec(),
+            ),
+            ValueRef::Time64(unit, value) => (
+                b'T',
+                normalize_to_micros(unit, value).to_le_bytes().to_vec(),
+            ),
+            ValueRef::Interval {
+                months,
+                days,
+                nanos,
+            } => {
+                let mut bytes = months.to_le_bytes().to_vec();
+                bytes.extend(days.to_le_bytes());
+                bytes.extend(nanos.to_le_bytes());
+                (b'v', bytes)
+            }
+            other => {
+                return Err(DuckyError::DatabaseError(format!(
+                    "Can't hash value: {:?}",
+                    other
+                )));
+            }
+        };
+        self.update_tagged(tag, &bytes);
+        Ok(())
+    }
+
+    /// Adds a tag and a length-prefixed byte string.
+    fn update_tagged(&mut self, tag: u8, bytes: &[u8]) {
+        self.sha.update([tag]);
+        self.update_length(bytes.len());
+        self.sha.update(bytes);
+    }
+
+    fn update_length(&mut self, length: usize) {
+        self.sha.update((length as u64).to_le_bytes());
+    }
+}
+
+/// Executes a SQL query and returns the rows as newline-delimited JSON.
+///
+/// Runs on a dirty CPU scheduler to avoid blocking the BEAM VM.
+///
+/// Rows are serialized by DuckDB's `to_json`, so values keep DuckDB's own
+/// JSON representation, e.g. intervals stay exact instead of being
+/// converted to terms first.
+///
+/// ## Arguments
+/// - `conn`: Connection resource
+/// - `sql`: SQL query string with optional `?` placeholders
+/// - `params_list`: Parameter values to bind into the query
+///
+/// ## Returns
+/// - `Ok(binary)` with one JSON object per line, each ending in a newline
+/// - `Err(DuckyError)` on failure
+#[rustler::nif(schedule = "DirtyCpu")]
+fn query_ndjson(
+    conn: ResourceArc<ConnectionResource>,
+    sql: String,
+    params_list: Vec<Term>,
+) -> Result<String, DuckyError> {
+    let connection = conn.lock()?;
+
+    let (sql, params) = bind_params(&sql, params_list)?;
+    let json_sql = format!(
+        "SELECT to_json(row_data)::VARCHAR FROM ({}) AS row_data",
+        sql
+    );
+    let mut stmt = connection.prepare(&json_sql)?;
+    let mut rows = stmt.query(param_refs(&params).as_slice())?;
+
+    let mut ndjson = String::new();
+    while let Some(row) = rows.next()? {
+        ndjson.push_str(&row.get::<_, String>(0)?);
+        ndjson.push('\n');
+    }
+    Ok(ndjson)
+}
+
+/// Opens a pool of connections to one database.
+///
+/// All connections share the same database instance, including for
+/// `:memory:`, so writes through one are visible to the others.
+///
+/// ## Arguments
+/// - `path`: Database file path or `:memory:` for in-memory database
+/// - `size`: Number of connections, at least 1
+///
+/// ## Returns
+/// - `Ok(ResourceArc<PoolResource>)` on success
+/// - `Err(ConnectionFailed)` if the size is 0 or a connection fails
+#[rustler::nif]
+fn pool_open(path: String, size: usize) -> Result<ResourceArc<PoolResource>, DuckyError> {
+    if size == 0 {
+        return Err(DuckyError::ConnectionFailed(
+            "Pool size must be at least 1".to_string(),
+        ));
+    }
+
+    let first = open_connection(&path)?;
+    let mut connections = Vec::with_capacity(size);
+    for _ in 1..size {
+        let connection = first
+            .try_clone()
+            .map_err(|e| DuckyError::ConnectionFailed(e.to_string()))?;
+        connections.push(connection);
+    }
+    connections.push(first);
+
+    Ok(ResourceArc::new(PoolResource {
+        idle: Mutex::new(connections),
+        available: Condvar::new(),
+    }))
+}
+
+/// Executes a SQL query on an idle connection from a pool.
+///
+/// Runs on a dirty CPU scheduler to avoid blocking the BEAM VM. Blocks
+/// until a connection is free when all of them are busy. Each call may
+/// use a different connection, so transactions can't span calls.
+///
+/// ## Arguments
+/// - `env`: NIF environment for term creation
+/// - `pool`: Pool resource
+/// - `sql`: SQL query string with optional `?` placeholders
+/// - `params_list`: Parameter values to bind
+///
+/// ## Returns
+/// - `Ok({columns, rows})` as for `execute_query`
+/// - `Err(DuckyError)` on failure
+#[rustler::nif(schedule = "DirtyCpu")]
+fn pool_execute<'a>(
+    env: Env<'a>,
+    pool: ResourceArc<PoolResource>,
+    sql: String,
+    params_list: Vec<Term<'a>>,
+) -> Result<(Vec<String>, Vec<Vec<Term<'a>>>), DuckyError> {
+    let (sql, params) = bind_params(&sql, params_list)?;
+    let connection = pool.checkout()?;
+
+    execute_statement(env, &connection, &sql, &param_refs(&params))
+}
+
+/// Creates an appender for bulk inserts into an existing table.
+///
+/// Runs on a dirty IO scheduler since it waits for any statement running on
+/// the connection.
+///
+/// Appended rows are buffered and only become visible once
+/// `appender_flush` is called or the appender is garbage collected.
+///
+/// ## Arguments
+/// - `conn`: Connection resource
+/// - `table_name`: Name of the table to insert into
+///
+/// ## Returns
+/// - `Ok(ResourceArc<AppenderResource>)` on success
+/// - `Err(DuckyError)` if the table doesn't exist
+#[rustler::nif(schedule = "DirtyIo")]
+fn appender_create(
+    conn: ResourceArc<ConnectionResource>,
+    table_name: String,
+) -> Result<ResourceArc<AppenderResource>, DuckyError> {
+    {
+        let connection = conn.lock()?;
+
+        // Validate the table up front rather than on the first flush
+        connection.appender(&table_name)?;
+    }
+
+    Ok(ResourceArc::new(AppenderResource {
+        conn,
+        table: table_name,
+        rows: Mutex::new(Vec::new()),
+    }))
+}
+
+/// Appends a row of values to the appender's buffer.
+///
+/// ## Arguments
+/// - `appender`: Appender resource
+/// - `values_list`: One value per table column, in column order
+///
+/// ## Returns
+/// - `Ok(nil)` on success
+/// - `Err(DuckyError)` if a value can't be converted
+#[rustler::nif]
+fn appender_append_row(
+    appender: ResourceArc<AppenderResource>,
+    values_list: Vec<Term>,
+) -> Result<rustler::Atom, DuckyError> {
+    let row = terms_to_params(values_list)?;
+
+    appender
+        .rows
+        .lock()
+        .map_err(|e| DuckyError::DatabaseError(format!("Appender mutex poisoned: {}", e)))?
+        .push(row);
+    Ok(atoms::nil())
+}
+
+/// Writes all buffered rows to the table.
+///
+/// Runs on a dirty CPU scheduler to avoid blocking the BEAM VM.
+///
+/// The buffer is cleared even when the write fails.
+///
+/// ## Arguments
+/// - `appender`: Appender resource
+///
+/// ## Returns
+/// - `Ok(nil)` on success
+/// - `Err(DatabaseError)` if a row doesn't match the table schema
+#[rustler::nif(schedule = "DirtyCpu")]
+fn appender_flush(appender: ResourceArc<AppenderResource>) -> Result<rustler::Atom, DuckyError> {
+    appender.flush()?;
+    Ok(atoms::nil())
+}
+
+/// Registers a scalar function whose results are computed by an Erlang
+/// process.
+///
+/// Runs on a dirty IO scheduler since it waits for any statement running on
+/// the connection.
+///
+/// The function takes any number of VARCHAR arguments and returns VARCHAR,
+/// so other types need casting in SQL, e.g. `my_fn(id::VARCHAR)::INTEGER`.
+/// For every row the handler process receives
+/// `{ducky_scalar_call, name, call, args}`, where args is a list of
+/// binaries with `null` for NULL, and must answer with
+/// `scalar_function_reply(call, result)`.
+///
+/// Each row costs a round trip between DuckDB and the handler while the
+/// query waits, so this suits small results and logic that can't be
+/// written in SQL. The handler must reply promptly, and it must not be the
+/// process running the query, which is blocked until the query finishes.
+///
+/// ## Arguments
+/// - `conn`: Connection resource
+/// - `name`: Name of the function in SQL
+/// - `pid`: Handler process
+///
+/// ## Returns
+/// - `Ok(nil)` on success
+/// - `Err(DatabaseError)` if the function can't be registered
+#[rustler::nif(schedule = "DirtyIo")]
+fn register_scalar_function(
+    conn: ResourceArc<ConnectionResource>,
+    name: String,
+    pid: rustler::LocalPid,
+) -> Result<rustler::Atom, DuckyError> {
+    let (calls, requests) = mpsc::channel::<ScalarCall>();
+    let function_name = name.clone();
+    thread::spawn(move || {
+        let mut owned_env = rustler::OwnedEnv::new();
+        // Runs until the function is dropped along with the connection
+        for call in requests {
+            let resource = ResourceArc::new(ScalarCallResource {
+                reply: Mutex::new(Some(call.reply)),
+            });
+            let _ = owned_env.send_and_clear(&pid, |env| {
+                let args: Vec<Term> = call
+                    .args
+                    .iter()
+                    .map(|arg| match arg {
+                        Some(value) => value.encode(env),
+                        None => atoms::null().encode(env),
+                    })
+                    .collect();
+                (
+                    atoms::ducky_scalar_call(),
+                    function_name.as_str(),
+                    resource,
+                    args,
+                )
+                    .encode(env)
+            });
+        }
+    });
+
+    let state = ErlangScalarState { calls: Some(calls) };
+    let connection = conn.lock()?;
+    connection.register_scalar_function_with_state::<ErlangScalar>(&name, &state)?;
+    Ok(atoms::nil())
+}
+
+/// Answers a call of a scalar function registered with
+/// `register_scalar_function`.
+///
+/// ## Arguments
+/// - `call`: Call resource from the `ducky_scalar_call` message
+/// - `result`: A binary, `null` or `nil` for NULL, or `{error, message}`
+///   to fail the query
+///
+/// ## Returns
+/// - `Ok(nil)` on success
+/// - `Err(DatabaseError)` if the call was already answered
+#[rustler::nif]
+fn scalar_function_reply(
+    call: ResourceArc<ScalarCallResource>,
+    result: Term,
+) -> Result<rustler::Atom, DuckyError> {
+    let reply = call
+        .reply
+        .lock()
+        .map_err(|e| DuckyError::DatabaseError(format!("Call mutex poisoned: {}", e)))?
+        .take()
+        .ok_or_else(|| {
+            DuckyError::DatabaseError("Scalar function call was already answered".to_string())
+        })?;
+
+    let value = if let Ok(value) = result.decode::<String>() {
+        Ok(Some(value))
+    } else if let Ok(atom) = result.decode::<rustler::Atom>() {
+        if atom == atoms::null() || atom == atoms::nil() {
+            Ok(None)
+        } else {
+            Err("Scalar function replies must be binaries or null".to_string())
+        }
+    } else {
+        match result.decode::<(rustler::Atom, String)>() {
+            Ok((tag, msg)) if tag == atoms::error() => Err(msg),
+            _ => Err("Scalar function replies must be binaries or null".to_string()),
+        }
+    };
+
+    // The query may have failed already, in which case nobody is waiting
+    let _ = reply.send(value);
+    Ok(atoms::nil())
+}
+
+/// Opens a cursor over the result of a query.
+///
+/// Runs on a dirty CPU scheduler to avoid blocking the BEAM VM.
+///
+/// Rows are converted to Erlang terms only as they are fetched with
+/// `cursor_fetch`. The query runs on a new connection to the same database,
+/// with the same schema search path, so `conn` is only locked while that
+/// connection is opened and stays usable while the cursor is open. The
+/// cursor sees committed data only: not the connection's open transaction
+/// or its temporary tables.
+///
+/// ## Arguments
+/// - `conn`: Connection resource
+/// - `sql`: SQL query string with optional `?` placeholders
+/// - `params_list`: Parameter values to bind
+///
+/// ## Returns
+/// - `Ok({cursor, columns})` where columns is a list of column names
+/// - `Err(DuckyError)` if the query fails
+#[rustler::nif(schedule = "DirtyCpu")]
+fn query_cursor(
+    conn: ResourceArc<ConnectionResource>,
+    sql: String,
+    params_list: Vec<Term>,
+) -> Result<(ResourceArc<CursorResource>, Vec<String>), DuckyError> {
+    let (sql, params) = bind_params(&sql, params_list)?;
+    let sql = sql.into_owned();
+    let connection = cursor_connection(&conn.lock()?)?;
+
+    let (ready_sender, ready_receiver) = mpsc::channel();
+    let (batch_sender, batch_receiver) = mpsc::sync_channel(1);
+
+    thread::spawn(move || {
+        if let Err(e) = stream_batches(&connection, &sql, &params, &ready_sender, batch_sender) {
+            let _ = ready_sender.send(Err(e));
+        }
+    });
+
+    let columns = ready_receiver.recv().map_err(|_| {
+        DuckyError::DatabaseError("Cursor worker exited unexpectedly".to_string())
+    })??;
+
+    let cursor = CursorResource {
+        state: Mutex::new(CursorState {
+            receiver: Some(batch_receiver),
+            batch: None,
+            batch_start: 0,
+            offset: 0,
+        }),
+    };
+    Ok((ResourceArc::new(cursor), columns))
+}
+
+/// Opens the connection a cursor runs its query on, resolving unqualified
+/// names the way `connection` does.
+fn cursor_connection(connection: &DuckDBConnection) -> Result<DuckDBConnection, DuckyError> {
+    let search_path: String =
+        connection.query_row("SELECT current_setting('search_path')", [], |row| {
+            row.get(0)
+        })?;
+
+    let cursor_connection = connection.try_clone()?;
+    cursor_connection.execute(
+        &format!("SET search_path = {}", quote_literal(&search_path)),
+        [],
+    )?;
+    Ok(cursor_connection)
+}
+
+/// Runs a query on the cursor worker thread and streams its record batches.
+///
+/// Column names are reported through `ready` once the query has executed.
+/// Streaming stops early when the cursor side of the channel is dropped.
+fn stream_batches(
+    connection: &DuckDBConnection,
+    sql: &str,
+    params: &[Value],
+    ready: &Sender<Result<Vec<String>, DuckyError>>,
+    batches: SyncSender<RecordBatch>,
+) -> Result<(), DuckyError> {
+    let mut stmt = connection.prepare(sql)?;
+    let arrow = stmt.query_arrow(param_refs(params).as_slice())?;
+
+    let columns = arrow
+        .get_schema()
+        .fields()
+        .iter()
+        .map(|field| field.name().to_string())
+        .collect();
+    let _ = ready.send(Ok(columns));
+
+    for batch in arrow {
+        if batches.send(batch).is_err() {
+            break;
+        }
+    }
+    Ok(())
+}
+
+/// Fetches the next rows from a cursor.
+///
+/// Runs on a dirty CPU scheduler to avoid blocking the BEAM VM.
+///
+/// ## Arguments
+/// - `env`: NIF environment for term creation
+/// - `cursor`: Cursor resource
+/// - `batch_size`: Maximum number of rows to return
+///
+/// ## Returns
+/// - `Ok({rows, more})` when `batch_size` rows were fetched
+/// - `Ok({rows, eof})` once the result is exhausted; later calls return no rows
+/// - `Err(DuckyError)` if a value can't be converted
+#[rustler::nif(schedule = "DirtyCpu")]
+fn cursor_fetch<'a>(
+    env: Env<'a>,
+    cursor: ResourceArc<CursorResource>,
+    batch_size: usize,
+) -> Result<(Vec<Vec<Term<'a>>>, rustler::Atom), DuckyError> {
+    let mut state = cursor
+        .state
+        .lock()
+        .map_err(|e| DuckyError::DatabaseError(format!("Cursor mutex poisoned: {}", e)))?;
+
+    let mut rows = Vec::new();
+    while rows.len() < batch_size {
+        let exhausted = state
+            .batch
+            .as_ref()
+            .is_none_or(|batch| state.offset >= batch.num_rows());
+
+        if exhausted {
+            if state.next_batch() {
+                continue;
+            }
+            return Ok((rows, atoms::eof()));
+        }
+
+        if let Some(batch) = state.batch.as_ref() {
+            let row = batch_row_to_terms(env, batch, state.offset)
+                .map_err(|_| DuckyError::DatabaseError("Failed to convert value".to_string()))?;
+            rows.push(row);
+        }
+        state.offset += 1;
+    }
+
+    Ok((rows, atoms::more()))
+}
+
+/// Reads part of a BLOB value from a cursor's result.
+///
+/// Runs on a dirty CPU scheduler to avoid blocking the BEAM VM.
+///
+/// Only the requested bytes are copied into a binary, so a large BLOB can
+/// be read in chunks without ever holding all of it in BEAM memory. The
+/// cursor moves forward to the record batch holding `row`; rows in earlier
+/// batches can't be read any more, and `cursor_fetch` carries on from the
+/// start of that batch.
+///
+/// ## Arguments
+/// - `env`: NIF environment for term creation
+/// - `cursor`: Cursor resource
+/// - `row`: Zero-based row index in the whole result
+/// - `column`: Zero-based column index
+/// - `offset`: Byte offset into the BLOB
+/// - `length`: Maximum number of bytes to read
+///
+/// ## Returns
+/// - `Ok(binary)` with up to `length` bytes; it is shorter at the end of the
+///   BLOB and empty when `offset` is past the end
+/// - `Ok(null)` if the value is NULL
+/// - `Err(DatabaseError)` if the row was already passed or is past the end
+///   of the result, or the column doesn't exist or isn't a BLOB
+#[rustler::nif(schedule = "DirtyCpu")]
+fn cell_blob_chunk<'a>(
+    env: Env<'a>,
+    cursor: ResourceArc<CursorResource>,
+    row: usize,
+    column: usize,
+    offset: usize,
+    length: usize,
+) -> Result<Term<'a>, DuckyError> {
+    use duckdb::arrow::array::AsArray;
+    use duckdb::arrow::datatypes::DataType;
+
+    let mut state = cursor
+        .state
+        .lock()
+        .map_err(|e| DuckyError::DatabaseError(format!("Cursor mutex poisoned: {}", e)))?;
+
+    if row < state.batch_start {
+        return Err(DuckyError::DatabaseError(format!(
+            "Row {} was already passed by the cursor",
+            row
+        )));
+    }
+    while state
+        .batch
+        .as_ref()
+        .is_none_or(|batch| row >= state.batch_start + batch.num_rows())
+    {
+        if !state.next_batch() {
+            break;
+        }
+    }
+
+    let batch = state.batch.as_ref().ok_or_else(|| {
+        DuckyError::DatabaseError(format!("Row {} is past the end of the result", row))
+    })?;
+    if column >= batch.num_columns() {
+        return Err(DuckyError::DatabaseError(format!(
+            "Column {} doesn't exist",
+            column
+        )));
+    }
+
+    let index = row - state.batch_start;
+    let array = batch.column(column);
+    if array.is_null(index) {
+        return Ok(atoms::null().encode(env));
+    }
+
+    let bytes = match array.data_type() {
+        DataType::Binary => array.as_binary::<i32>().value(index),
+        DataType::LargeBinary => array.as_binary::<i64>().value(index),
+        DataType::FixedSizeBinary(_) => array.as_fixed_size_binary().value(index),
+        _ => {
+            return Err(DuckyError::DatabaseError(format!(
+                "Column {} is not a BLOB",
+                column
+            )));
+        }
+    };
+
+    let start = offset.min(bytes.len());
+    let end = start.saturating_add(length).min(bytes.len());
+    Ok(blob_to_term(env, &bytes[start..end]))
+}
+
+/// Closes a cursor and releases the connection it runs on.
+///
+/// ## Arguments
+/// - `cursor`: Cursor resource
+///
+/// ## Returns
+/// - `Ok(nil)` on success
+#[rustler::nif]
+fn cursor_close(cursor: ResourceArc<CursorResource>) -> Result<rustler::Atom, DuckyError> {
+    cursor
+        .state
+        .lock()
+        .map_err(|e| DuckyError::DatabaseError(format!("Cursor mutex poisoned: {}", e)))?
+        .finish();
+    Ok(atoms::nil())
+}
+
+/// Converts one row of a record batch into Erlang terms.
+fn batch_row_to_terms<'a>(
+    env: Env<'a>,
+    batch: &RecordBatch,
+    row_idx: usize,
+) -> NifResult<Vec<Term<'a>>> {
+    batch
+        .columns()
+        .iter()
+        .map(|column| {
+            if column.is_null(row_idx) {
+                Ok(atoms::null().encode(env))
+            } else {
+                element_to_term(env, column.as_ref(), row_idx)
+            }
+        })
+        .collect()
+}
+
+/// Converts Arrow TimeUnit to DuckDB TimeUnit.
+fn arrow_to_duckdb_time_unit(
+    arrow_unit: duckdb::arrow::datatypes::TimeUnit,
+) -> duckdb::types::TimeUnit {
+    use duckdb::arrow::datatypes::TimeUnit as ArrowUnit;
+    use duckdb::types::TimeUnit as DuckUnit;
+    match arrow_unit {
+        ArrowUnit::Second => DuckUnit::Second,
+        ArrowUnit::Millisecond => DuckUnit::Millisecond,
+        ArrowUnit::Microsecond => DuckUnit::Microsecond,
+        ArrowUnit::Nanosecond => DuckUnit::Nanosecond,
+    }
+}
+
+/// Normalizes a temporal value to microseconds based on TimeUnit.
+fn normalize_to_micros(time_unit: duckdb::types::TimeUnit, value: i64) -> i64 {
+    use duckdb::types::TimeUnit;
+    match time_unit {
+        TimeUnit::Second => value * 1_000_000,
+        TimeUnit::Millisecond => value * 1_000,
+        TimeUnit::Microsecond => value,
+        TimeUnit::Nanosecond => value / 1_000,
+    }
+}
+
+/// Encodes a float, using the atoms `nan`, `infinity` and
+/// `negative_infinity` for values Erlang floats can't represent.
+fn float_to_term(env: Env<'_>, f: f64) -> Term<'_> {
+    if f.is_nan() {
+        atoms::nan().encode(env)
+    } else if f == f64::INFINITY {
+        atoms::infinity().encode(env)
+    } else if f == f64::NEG_INFINITY {
+        atoms::negative_infinity().encode(env)
+    } else {
+        f.encode(env)
+    }
+}
+
+/// Copies a BLOB into a new Erlang binary.
+///
+/// The bytes live in DuckDB's result buffers, which are freed with the
+/// result, so one copy can't be avoided. Writing straight into a
+/// `NewBinary` keeps it to that one copy: small blobs become heap binaries
+/// and larger ones reference-counted binaries that are shared, not copied,
+/// when sent between processes.
+fn blob_to_term<'a>(env: Env<'a>, bytes: &[u8]) -> Term<'a> {
+    let mut binary = rustler::NewBinary::new(env, bytes.len());
+    binary.as_mut_slice().copy_from_slice(bytes);
+    rustler::Binary::from(binary).to_term(env)
+}
+
+/// Converts a value to an Erlang term, using its column's Arrow type to
+/// tag TIMESTAMPTZ values as `{timestamptz, micros}`.
+///
+/// DuckDB stores TIMESTAMPTZ as UTC microseconds like a plain TIMESTAMP.
+/// Only the Arrow type's timezone tells them apart, and `ValueRef` drops it.
+fn typed_value_to_term<'a>(
+    env: Env<'a>,
+    data_type: &duckdb::arrow::datatypes::DataType,
+    value: ValueRef<'_>,
+) -> NifResult<Term<'a>> {
+    use duckdb::arrow::datatypes::DataType;
+
+    match (data_type, value) {
+        (DataType::Timestamp(_, Some(_)), ValueRef::Timestamp(time_unit, value)) => {
+            let micros = normalize_to_micros(time_unit, value);
+            Ok((atoms::timestamptz(), micros).encode(env))
+        }
+        (_, value) => value_to_term(env, value),
+    }
+}
+
+/// Encodes a decimal as `{decimal, unscaled, scale}`.
+///
+/// Unscaled values wider than i64 are sent as strings.
+fn decimal_to_term(env: Env<'_>, unscaled: i128, scale: u32) -> Term<'_> {
+    match i64::try_from(unscaled) {
+        Ok(small) => (atoms::decimal(), small, scale).encode(env),
+        Err(_) => (atoms::decimal(), unscaled.to_string(), scale).encode(env),
+    }
+}
+
+/// Converts a DuckDB ValueRef to an Erlang term.
+fn value_to_term<'a, 'b>(env: Env<'a>, value: ValueRef<'b>) -> NifResult<Term<'a>> {
     match value {
         ValueRef::Null => Ok(atoms::null().encode(env)),
         ValueRef::Boolean(b) => Ok(b.encode(env)),
@@ -197,31 +5108,34 @@ fn value_to_term<'a, 'b>(env: Env<'a>, value: ValueRef<'b>) -> NifResult<Term<'a
         ValueRef::SmallInt(i) => Ok(i.encode(env)),
         ValueRef::Int(i) => Ok(i.encode(env)),
         ValueRef::BigInt(i) => Ok(i.encode(env)),
-        ValueRef::HugeInt(i) => Ok(i.encode(env)),
+        ValueRef::HugeInt(i) => match i64::try_from(i) {
+            Ok(small) => Ok(small.encode(env)),
+            // Rustler builds an Erlang bignum for values beyond i64
+            Err(_) => Ok(i.encode(env)),
+        },
         ValueRef::UTinyInt(i) => Ok((i as i32).encode(env)),
         ValueRef::USmallInt(i) => Ok((i as i32).encode(env)),
         ValueRef::UInt(i) => Ok((i as i64).encode(env)),
-        ValueRef::UBigInt(i) => match i64::try_from(i) {
-            Ok(signed) => Ok(signed.encode(env)),
-            Err(_) => Err(rustler::Error::Term(Box::new(format!(
-                "Integer overflow: UBigInt value {} exceeds i64::MAX ({})",
-                i,
-                i64::MAX
-            )))),
-        },
-        ValueRef::Float(f) => Ok(f.encode(env)),
-        ValueRef::Double(f) => Ok(f.encode(env)),
+        // Values above i64::MAX become Erlang bignums
+        ValueRef::UBigInt(i) => Ok(i.encode(env)),
+        ValueRef::Decimal(d) => Ok(decimal_to_term(env, d.mantissa(), d.scale())),
+        ValueRef::Float(f) => Ok(float_to_term(env, f64::from(f))),
+        ValueRef::Double(f) => Ok(float_to_term(env, f)),
+        // UUID columns also arrive here, already formatted as canonical
+        // hyphenated strings by DuckDB's Arrow export
         ValueRef::Text(s) => {
             let text = std::str::from_utf8(s)
                 .map_err(|_| rustler::Error::Term(Box::new("Invalid UTF-8")))?;
             Ok(text.encode(env))
         }
-        ValueRef::Blob(b) => Ok(b.encode(env)),
+        ValueRef::Blob(b) => Ok(blob_to_term(env, b)),
         ValueRef::Timestamp(time_unit, value) => {
             let micros = normalize_to_micros(time_unit, value);
             Ok((atoms::timestamp(), micros).encode(env))
         }
         ValueRef::Date32(days) => Ok((atoms::date(), days).encode(env)),
+        // TIMETZ columns also arrive here as their local time of day, since
+        // DuckDB's Arrow export drops the offset
         ValueRef::Time64(time_unit, value) => {
             let micros = normalize_to_micros(time_unit, value);
             Ok((atoms::time(), micros).encode(env))
@@ -231,18 +5145,16 @@ fn value_to_term<'a, 'b>(env: Env<'a>, value: ValueRef<'b>) -> NifResult<Term<'a
             days,
             nanos,
         } => {
-            // Convert to total nanoseconds (approximate for months)
-            // 1 month ≈ 30 days
-            let month_nanos = (months as i64) * 30 * 24 * 60 * 60 * 1_000_000_000;
-            let day_nanos = (days as i64) * 24 * 60 * 60 * 1_000_000_000;
-            let total_nanos = month_nanos + day_nanos + nanos;
-            Ok((atoms::interval(), total_nanos).encode(env))
+            // Keep the components separate: months and days have no fixed
+            // length, so folding them into nanoseconds would lose information
+            Ok((atoms::interval(), months, days, nanos).encode(env))
         }
         ValueRef::Struct(struct_array, idx) => encode_struct(env, struct_array, idx),
         ValueRef::List(list_type, row_idx) => encode_list(env, list_type, row_idx),
-        ValueRef::Array(_array, _row_idx) => Err(rustler::Error::Term(Box::new(
-            "DuckDB ARRAY type not yet supported",
-        ))),
+        ValueRef::Array(array, row_idx) => encode_fixed_size_list(env, array, row_idx),
+        ValueRef::Map(map_array, row_idx) => encode_map(env, map_array, row_idx),
+        ValueRef::Enum(enum_type, row_idx) => encode_enum(env, enum_type, row_idx),
+        ValueRef::Union(array, row_idx) => encode_union(env, array.as_ref(), row_idx),
         other => {
             let type_name = format!("Unsupported ValueRef variant: {:?}", other);
             Err(rustler::Error::Term(Box::new(type_name)))
@@ -304,6 +5216,12 @@ fn arrow_element_to_value_ref<'b>(
             let arr = array.as_primitive::<duckdb::arrow::datatypes::Float64Type>();
             Ok(ValueRef::Double(arr.value(elem_idx)))
         }
+        DataType::Decimal128(_, scale) => {
+            let arr = array.as_primitive::<duckdb::arrow::datatypes::Decimal128Type>();
+            rust_decimal::Decimal::try_from_i128_with_scale(arr.value(elem_idx), *scale as u32)
+                .map(ValueRef::Decimal)
+                .map_err(|e| format!("Decimal out of range: {}", e))
+        }
         DataType::Utf8 => {
             let arr = array.as_string::<i32>();
             Ok(ValueRef::Text(arr.value(elem_idx).as_bytes()))
@@ -312,6 +5230,10 @@ fn arrow_element_to_value_ref<'b>(
             let arr = array.as_binary::<i32>();
             Ok(ValueRef::Blob(arr.value(elem_idx)))
         }
+        DataType::FixedSizeBinary(_) => {
+            let arr = array.as_fixed_size_binary();
+            Ok(ValueRef::Blob(arr.value(elem_idx)))
+        }
         DataType::Struct(_) => {
             let child_struct = array.as_struct();
             Ok(ValueRef::Struct(child_struct, elem_idx))
@@ -320,6 +5242,30 @@ fn arrow_element_to_value_ref<'b>(
             let child_list = array.as_list();
             Ok(ValueRef::List(ListType::Regular(child_list), elem_idx))
         }
+        DataType::LargeList(_) => {
+            let child_list = array.as_list::<i64>();
+            Ok(ValueRef::List(ListType::Large(child_list), elem_idx))
+        }
+        DataType::FixedSizeList(_, _) => {
+            let child_array = array.as_fixed_size_list();
+            Ok(ValueRef::Array(child_array, elem_idx))
+        }
+        DataType::Map(_, _) => {
+            let child_map = array.as_map();
+            Ok(ValueRef::Map(child_map, elem_idx))
+        }
+        DataType::Dictionary(key_type, _) => {
+            use duckdb::arrow::datatypes::{UInt8Type, UInt16Type, UInt32Type};
+            use duckdb::types::EnumType;
+
+            let enum_type = match key_type.as_ref() {
+                DataType::UInt8 => EnumType::UInt8(array.as_dictionary::<UInt8Type>()),
+                DataType::UInt16 => EnumType::UInt16(array.as_dictionary::<UInt16Type>()),
+                DataType::UInt32 => EnumType::UInt32(array.as_dictionary::<UInt32Type>()),
+                other => return Err(format!("Unsupported enum key type: {:?}", other)),
+            };
+            Ok(ValueRef::Enum(enum_type, elem_idx))
+        }
         DataType::Timestamp(time_unit, _) => {
             let arr = array.as_primitive::<duckdb::arrow::datatypes::TimestampMicrosecondType>();
             let duckdb_unit = arrow_to_duckdb_time_unit(*time_unit);
@@ -383,137 +5329,670 @@ fn encode_list<'a, 'b>(
         }
     };
 
-    let mut elements = Vec::new();
+    encode_elements(env, values_array.as_ref(), start, end)
+}
+
+/// Encodes a DuckDB fixed-size ARRAY as an Erlang list.
+fn encode_fixed_size_list<'a>(
+    env: Env<'a>,
+    array: &duckdb::arrow::array::FixedSizeListArray,
+    row_idx: usize,
+) -> NifResult<Term<'a>> {
+    if array.is_null(row_idx) {
+        return Ok(atoms::null().encode(env));
+    }
+
+    let start = array.value_offset(row_idx) as usize;
+    let end = start + array.value_length() as usize;
+    encode_elements(env, array.values().as_ref(), start, end)
+}
+
+/// Encodes the `start..end` slice of a child array as an Erlang list.
+///
+/// Null elements become `null` atoms.
+fn encode_elements<'a>(
+    env: Env<'a>,
+    values_array: &dyn Array,
+    start: usize,
+    end: usize,
+) -> NifResult<Term<'a>> {
+    let mut elements = Vec::with_capacity(end - start);
     for elem_idx in start..end {
         if values_array.is_null(elem_idx) {
             elements.push(atoms::null().encode(env));
         } else {
-            let value_ref = arrow_element_to_value_ref(values_array.as_ref(), elem_idx)
-                .map_err(|e| rustler::Error::Term(Box::new(e)))?;
-            let term = value_to_term(env, value_ref)?;
-            elements.push(term);
+            elements.push(element_to_term(env, values_array, elem_idx)?);
         }
     }
 
     Ok(elements.encode(env))
 }
 
-/// Encodes a DuckDB struct as an Erlang map with recursive field encoding.
-fn encode_struct<'a>(
+/// Encodes a DuckDB MAP as an Erlang map with recursive key and value encoding.
+///
+/// An entry whose key is NULL is stored under `{null_key, position}` so that
+/// it can't collide with other entries.
+fn encode_map<'a>(
+    env: Env<'a>,
+    map_array: &duckdb::arrow::array::MapArray,
+    row_idx: usize,
+) -> NifResult<Term<'a>> {
+    use rustler::types::map::map_new;
+
+    if map_array.is_null(row_idx) {
+        return Ok(atoms::null().encode(env));
+    }
+
+    let offsets = map_array.value_offsets();
+    let start = offsets[row_idx] as usize;
+    let end = offsets[row_idx + 1] as usize;
+    let keys = map_array.keys();
+    let values = map_array.values();
+
+    let mut map = map_new(env);
+    for entry_idx in start..end {
+        let key = if keys.is_null(entry_idx) {
+            (atoms::null_key(), entry_idx - start).encode(env)
+        } else {
+            element_to_term(env, keys.as_ref(), entry_idx)?
+        };
+        let value = if values.is_null(entry_idx) {
+            atoms::null().encode(env)
+        } else {
+            element_to_term(env, values.as_ref(), entry_idx)?
+        };
+        map = map.map_put(key, value)?;
+    }
+
+    Ok(map)
+}
+
+/// Encodes a DuckDB ENUM value as its string label.
+fn encode_enum<'a>(
+    env: Env<'a>,
+    enum_type: duckdb::types::EnumType<'_>,
+    row_idx: usize,
+) -> NifResult<Term<'a>> {
+    use duckdb::arrow::array::AsArray;
+    use duckdb::types::EnumType;
+
+    let (key, labels) = match enum_type {
+        EnumType::UInt8(dict) => (dict.key(row_idx), dict.values()),
+        EnumType::UInt16(dict) => (dict.key(row_idx), dict.values()),
+        EnumType::UInt32(dict) => (dict.key(row_idx), dict.values()),
+    };
+
+    match key {
+        Some(key) => Ok(labels.as_string::<i32>().value(key).encode(env)),
+        None => Ok(atoms::null().encode(env)),
+    }
+}
+
+/// Encodes a UNION value as `{union, member, value}`, where `member` is
+/// the name of the active member as an atom.
+fn encode_union<'a>(env: Env<'a>, array: &dyn Array, row_idx: usize) -> NifResult<Term<'a>> {
+    use duckdb::arrow::array::AsArray;
+    use duckdb::arrow::datatypes::DataType;
+
+    let union_array = array.as_union();
+    let type_id = union_array.type_id(row_idx);
+    let member = match union_array.data_type() {
+        DataType::Union(fields, _) => fields
+            .iter()
+            .find(|(id, _)| *id == type_id)
+            .map(|(_, field)| field.name().as_str()),
+        _ => None,
+    }
+    .ok_or_else(|| rustler::Error::Term(Box::new(format!("Unknown union member {}", type_id))))?;
+
+    // Member names come from the column type, so the atom table stays bounded
+    let tag = rustler::Atom::from_str(env, member)?;
+    let child = union_array.child(type_id);
+    let offset = union_array.value_offset(row_idx);
+    let value = if child.is_null(offset) {
+        atoms::null().encode(env)
+    } else {
+        element_to_term(env, child.as_ref(), offset)?
+    };
+
+    Ok((atoms::union(), tag, value).encode(env))
+}
+
+/// Encodes a single non-null element of an Arrow array.
+fn element_to_term<'a>(env: Env<'a>, array: &dyn Array, elem_idx: usize) -> NifResult<Term<'a>> {
+    // ValueRef::Union needs an owned ArrayRef, so nested unions are
+    // encoded directly
+    if let duckdb::arrow::datatypes::DataType::Union(_, _) = array.data_type() {
+        return encode_union(env, array, elem_idx);
+    }
+
+    // Decimals are encoded from their unscaled value, which can be wider than
+    // ValueRef::Decimal holds. Nested HUGEINT values arrive as zero-scale
+    // decimals too, since that is how DuckDB exports them to Arrow.
+    if let duckdb::arrow::datatypes::DataType::Decimal128(_, scale) = array.data_type() {
+        use duckdb::arrow::array::AsArray;
+
+        let arr = array.as_primitive::<duckdb::arrow::datatypes::Decimal128Type>();
+        return Ok(decimal_to_term(env, arr.value(elem_idx), *scale as u32));
+    }
+
+    let value_ref = arrow_element_to_value_ref(array, elem_idx)
+        .map_err(|e| rustler::Error::Term(Box::new(e)))?;
+    typed_value_to_term(env, array.data_type(), value_ref)
+}
+
+/// Encodes a DuckDB struct as an Erlang map with recursive field encoding.
+fn encode_struct<'a>(
+    env: Env<'a>,
+    struct_array: &duckdb::arrow::array::StructArray,
+    row_idx: usize,
+) -> NifResult<Term<'a>> {
+    use rustler::types::map::map_new;
+
+    let mut map = map_new(env);
+
+    // Iterate over struct fields
+    for (field_idx, field) in struct_array.columns().iter().enumerate() {
+        // Get field name from schema
+        let field_name = struct_array
+            .fields()
+            .get(field_idx)
+            .map(|f| f.name().as_str())
+            .unwrap_or("unknown");
+
+        if field.is_null(row_idx) {
+            map = map.map_put(field_name.encode(env), atoms::null().encode(env))?;
+            continue;
+        }
+
+        // Shares element decoding with lists and maps, so containers nest
+        // to any depth and unsupported types fail instead of becoming null
+        let term_value = element_to_term(env, field.as_ref(), row_idx)?;
+        map = map.map_put(field_name.encode(env), term_value)?;
+    }
+
+    Ok(map)
+}
+
+/// Core statement execution logic for all queries.
+fn execute_statement<'a>(
+    env: Env<'a>,
+    connection: &DuckDBConnection,
+    sql: &str,
+    params: &[&dyn duckdb::types::ToSql],
+) -> Result<(Vec<String>, Vec<Vec<Term<'a>>>), DuckyError> {
+    with_statement(connection, sql, |stmt| run_statement(env, stmt, params))
+}
+
+/// Like `execute_statement`, but reuses a statement from the connection's
+/// statement cache.
+fn execute_cached_statement<'a>(
+    env: Env<'a>,
+    connection: &DuckDBConnection,
+    sql: &str,
+    params: &[&dyn duckdb::types::ToSql],
+) -> Result<(Vec<String>, Vec<Vec<Term<'a>>>), DuckyError> {
+    with_cached_statement(connection, sql, |stmt| run_statement(env, stmt, params))
+}
+
+/// Prepares a statement and runs `run` with it, attaching the SQL to any
+/// error.
+fn with_statement<T>(
+    connection: &DuckDBConnection,
+    sql: &str,
+    run: impl FnOnce(&mut duckdb::Statement<'_>) -> Result<T, DuckyError>,
+) -> Result<T, DuckyError> {
+    reject_empty_statement(sql)?;
+    connection
+        .prepare(sql)
+        .map_err(DuckyError::from)
+        .and_then(|mut stmt| run(&mut stmt))
+        .map_err(|e| e.in_statement(sql))
+}
+
+/// Like `with_statement`, but reuses a statement from the connection's
+/// statement cache.
+///
+/// Preparing a script runs every statement but the last, so a cache hit
+/// would silently skip them. Scripts are therefore prepared afresh on
+/// every call.
+fn with_cached_statement<T>(
+    connection: &DuckDBConnection,
+    sql: &str,
+    run: impl FnOnce(&mut duckdb::Statement<'_>) -> Result<T, DuckyError>,
+) -> Result<T, DuckyError> {
+    if split_statements(sql).len() != 1 {
+        return with_statement(connection, sql, run);
+    }
+    connection
+        .prepare_cached(sql)
+        .map_err(DuckyError::from)
+        .and_then(|mut stmt| run(&mut *stmt))
+        .map_err(|e| e.in_statement(sql))
+}
+
+/// Fails with a clear error for SQL that is empty or only whitespace and
+/// comments, which DuckDB would otherwise reject with a cryptic one.
+fn reject_empty_statement(sql: &str) -> Result<(), DuckyError> {
+    if split_statements(sql).is_empty() {
+        return Err(DuckyError::QuerySyntaxError("Empty statement".to_string()));
+    }
+    Ok(())
+}
+
+/// Runs an already prepared statement and collects its result.
+fn run_statement<'a>(
+    env: Env<'a>,
+    stmt: &mut duckdb::Statement<'_>,
+    params: &[&dyn duckdb::types::ToSql],
+) -> Result<(Vec<String>, Vec<Vec<Term<'a>>>), DuckyError> {
+    run_statement_up_to(env, stmt, params, usize::MAX, RowEncoding::default(), None)
+        .map(|(columns, rows, _)| (columns, rows))
+}
+
+/// Runs an already prepared statement and collects at most `max_rows` rows
+/// of its result, reporting whether any rows were left over.
+///
+/// When `timing` is given, the time spent executing the statement and
+/// converting its rows is recorded in it.
+fn run_statement_up_to<'a>(
+    env: Env<'a>,
+    stmt: &mut duckdb::Statement<'_>,
+    params: &[&dyn duckdb::types::ToSql],
+    max_rows: usize,
+    encoding: RowEncoding,
+    timing: Option<&mut StatementTiming>,
+) -> Result<(Vec<String>, Vec<Vec<Term<'a>>>, bool), DuckyError> {
+    let started = Instant::now();
+    // Every statement runs as a query, DDL and DML included, so it runs
+    // exactly once and a failure is returned as DuckDB reported it
+    let mut rows_result = stmt.query(params)?;
+    let executed = Instant::now();
+    let (raw_rows, truncated) = collect_rows_up_to(env, &mut rows_result, max_rows, encoding)?;
+    let detected_column_count = raw_rows.first().map_or(0, |row| row.len());
+
+    // Get column names after consuming rows
+    let column_names: Vec<String> = (0..detected_column_count)
+        .filter_map(|i| stmt.column_name(i).ok().map(|s| s.to_string()))
+        .collect();
+
+    if let Some(timing) = timing {
+        timing.execute = executed - started;
+        timing.fetch = executed.elapsed();
+    }
+
+    Ok((column_names, raw_rows, truncated))
+}
+
+/// How long each phase of running a statement took.
+#[derive(Default)]
+struct StatementTiming {
+    /// Getting the statement from the cache, or preparing it on a miss.
+    prepare: Duration,
+    /// Executing the statement up to its first result.
+    execute: Duration,
+    /// Converting the rows of the result into Erlang terms.
+    fetch: Duration,
+}
+
+impl StatementTiming {
+    /// Returns the phases as a map from their names to microseconds.
+    fn to_micros(&self) -> HashMap<&'static str, u64> {
+        let micros = |duration: Duration| u64::try_from(duration.as_micros()).unwrap_or(u64::MAX);
+        HashMap::from([
+            ("prepare", micros(self.prepare)),
+            ("execute", micros(self.execute)),
+            ("fetch", micros(self.fetch)),
+        ])
+    }
+}
+
+/// Options accepted by `execute_query_opts` and `execute_query_timed`.
+#[derive(Default)]
+struct QueryOptions {
+    /// Whether empty and repeated column names are replaced by `column_N`.
+    positional_names: bool,
+    encoding: RowEncoding,
+}
+
+impl QueryOptions {
+    /// Reads the options from an options map, rejecting unknown keys.
+    fn parse(options: &HashMap<String, Term<'_>>) -> Result<Self, DuckyError> {
+        let invalid =
+            |key: &str| DuckyError::QuerySyntaxError(format!("Invalid value for {}", key));
+
+        let mut parsed = QueryOptions::default();
+        for (key, value) in sorted_options(options) {
+            match key.as_str() {
+                "positional_names" => {
+                    parsed.positional_names = value.decode::<bool>().map_err(|_| invalid(key))?;
+                }
+                "integers_as_strings" => {
+                    parsed.encoding.integers_as_strings =
+                        value.decode::<bool>().map_err(|_| invalid(key))?;
+                }
+                _ => {
+                    return Err(DuckyError::QuerySyntaxError(format!(
+                        "Unknown query option: {}",
+                        key
+                    )));
+                }
+            }
+        }
+        Ok(parsed)
+    }
+
+    /// Applies the naming options to the column names of a result.
+    fn column_names(&self, columns: Vec<String>) -> Vec<String> {
+        if self.positional_names {
+            positional_fallback_names(columns)
+        } else {
+            columns
+        }
+    }
+}
+
+/// Converts every remaining row of a result into Erlang terms.
+fn collect_rows<'a>(
     env: Env<'a>,
-    struct_array: &duckdb::arrow::array::StructArray,
-    row_idx: usize,
-) -> NifResult<Term<'a>> {
-    use rustler::types::map::map_new;
+    rows_result: &mut duckdb::Rows<'_>,
+) -> Result<Vec<Vec<Term<'a>>>, DuckyError> {
+    collect_rows_up_to(env, rows_result, usize::MAX, RowEncoding::default()).map(|(rows, _)| rows)
+}
 
-    let mut map = map_new(env);
+/// How `collect_rows_up_to` encodes the values of a result.
+#[derive(Clone, Copy, Default)]
+struct RowEncoding {
+    /// Whether integer columns are sent as decimal strings, which is
+    /// cheaper than building bignums for wide integers.
+    integers_as_strings: bool,
+}
 
-    // Iterate over struct fields
-    for (field_idx, field) in struct_array.columns().iter().enumerate() {
-        // Get field name from schema
-        let field_name = struct_array
-            .fields()
-            .get(field_idx)
-            .map(|f| f.name().as_str())
-            .unwrap_or("unknown");
+/// Converts up to `max_rows` rows of a result into Erlang terms, reporting
+/// whether the result had more.
+///
+/// BIT columns reach Arrow as plain binaries, so they are told apart from
+/// BLOBs by their DuckDB type and returned as strings of `'0'` and `'1'`.
+/// Likewise, DuckDB exports both HUGEINT and zero-scale DECIMAL columns as
+/// zero-scale Arrow decimals, which read back as HUGEINT, so the latter are
+/// turned back into decimals here.
+fn collect_rows_up_to<'a>(
+    env: Env<'a>,
+    rows_result: &mut duckdb::Rows<'_>,
+    max_rows: usize,
+    encoding: RowEncoding,
+) -> Result<(Vec<Vec<Term<'a>>>, bool), DuckyError> {
+    use duckdb::core::LogicalTypeId;
 
-        if field.is_null(row_idx) {
-            map = map.map_put(field_name.encode(env), atoms::null().encode(env))?;
-            continue;
+    let mut raw_rows = Vec::new();
+    let mut column_types: Option<Vec<_>> = None;
+
+    while let Some(row) = rows_result.next()? {
+        if raw_rows.len() == max_rows {
+            return Ok((raw_rows, true));
         }
 
-        match arrow_element_to_value_ref(field.as_ref(), row_idx) {
-            Ok(value_ref) => {
-                let term_value = value_to_term(env, value_ref)?;
-                map = map.map_put(field_name.encode(env), term_value)?;
-            }
-            Err(_) => {
-                map = map.map_put(field_name.encode(env), atoms::null().encode(env))?;
+        let stmt = row.as_ref();
+        let column_count = stmt.column_count();
+        let column_types = column_types.get_or_insert_with(|| {
+            (0..column_count)
+                .map(|i| (stmt.column_type(i), stmt.column_logical_type(i).id()))
+                .collect()
+        });
+
+        let mut row_values = Vec::with_capacity(column_count);
+        for (i, (data_type, logical_type)) in column_types.iter().enumerate() {
+            let term = match row.get_ref(i)? {
+                ValueRef::Blob(bits) if *logical_type == LogicalTypeId::Bit => {
+                    Ok(bits_to_string(bits).encode(env))
+                }
+                ValueRef::HugeInt(unscaled) if *logical_type == LogicalTypeId::Decimal => {
+                    Ok(decimal_to_term(env, unscaled, 0))
+                }
+                value if encoding.integers_as_strings => match integer_to_string(&value) {
+                    Some(digits) => Ok(digits.encode(env)),
+                    None => typed_value_to_term(env, data_type, value),
+                },
+                value => typed_value_to_term(env, data_type, value),
             }
+            .map_err(|_| DuckyError::DatabaseError("Failed to convert value".to_string()))?;
+            row_values.push(term);
         }
+
+        raw_rows.push(row_values);
     }
 
-    Ok(map)
+    Ok((raw_rows, false))
 }
 
-/// Core statement execution logic for all queries.
-fn execute_statement<'a>(
-    env: Env<'a>,
-    connection: &DuckDBConnection,
-    sql: &str,
-    params: &[&dyn duckdb::types::ToSql],
-) -> Result<(Vec<String>, Vec<Vec<Term<'a>>>), DuckyError> {
-    let mut stmt = connection.prepare(sql)?;
+/// Formats an integer value as a decimal string, or returns `None` for
+/// values of other types.
+fn integer_to_string(value: &ValueRef<'_>) -> Option<String> {
+    match value {
+        ValueRef::TinyInt(i) => Some(i.to_string()),
+        ValueRef::SmallInt(i) => Some(i.to_string()),
+        ValueRef::Int(i) => Some(i.to_string()),
+        ValueRef::BigInt(i) => Some(i.to_string()),
+        ValueRef::HugeInt(i) => Some(i.to_string()),
+        ValueRef::UTinyInt(i) => Some(i.to_string()),
+        ValueRef::USmallInt(i) => Some(i.to_string()),
+        ValueRef::UInt(i) => Some(i.to_string()),
+        ValueRef::UBigInt(i) => Some(i.to_string()),
+        _ => None,
+    }
+}
 
-    // Try executing as a query
-    // DuckDB will return an error if it's not a result-returning statement
-    match stmt.query(params) {
-        Ok(mut rows_result) => {
-            // This is a result-returning statement
-            let mut raw_rows = Vec::new();
-            let mut detected_column_count = 0;
-
-            while let Some(row) = rows_result.next()? {
-                if detected_column_count == 0 {
-                    detected_column_count = row.as_ref().column_count();
-                }
+/// Formats DuckDB's BIT storage as a string of `'0'` and `'1'` characters.
+///
+/// The first byte counts the padding bits at the start of the data, which
+/// follows most significant bit first.
+fn bits_to_string(bytes: &[u8]) -> String {
+    let Some((&padding, data)) = bytes.split_first() else {
+        return String::new();
+    };
 
-                let mut row_values = Vec::new();
-                for i in 0..detected_column_count {
-                    let value = row.get_ref(i)?;
-                    let term = value_to_term(env, value).map_err(|_| {
-                        DuckyError::DatabaseError("Failed to convert value".to_string())
-                    })?;
-                    row_values.push(term);
-                }
+    data.iter()
+        .flat_map(|byte| {
+            (0..8)
+                .rev()
+                .map(move |bit| if (byte >> bit) & 1 == 1 { '1' } else { '0' })
+        })
+        .skip(usize::from(padding))
+        .collect()
+}
+
+/// Makes column names unique by suffixing repeats with `_2`, `_3`, and so on.
+fn disambiguate_columns(columns: Vec<String>) -> Vec<String> {
+    let mut seen = HashSet::with_capacity(columns.len());
+
+    columns
+        .into_iter()
+        .map(|name| {
+            let mut candidate = name.clone();
+            let mut suffix = 2;
+            while !seen.insert(candidate.clone()) {
+                candidate = format!("{}_{}", name, suffix);
+                suffix += 1;
+            }
+            candidate
+        })
+        .collect()
+}
 
-                raw_rows.push(row_values);
+/// Replaces empty and repeated column names with `column_N`, where `N` is
+/// the column's zero-based position.
+fn positional_fallback_names(columns: Vec<String>) -> Vec<String> {
+    let mut seen = HashSet::with_capacity(columns.len());
+
+    columns
+        .into_iter()
+        .enumerate()
+        .map(|(i, name)| {
+            if name.is_empty() || !seen.insert(name.clone()) {
+                format!("column_{}", i)
+            } else {
+                name
             }
+        })
+        .collect()
+}
 
-            // Get column names after consuming rows
-            let column_names: Vec<String> = (0..detected_column_count)
-                .filter_map(|i| stmt.column_name(i).ok().map(|s| s.to_string()))
-                .collect();
+/// Zips each row with the column names into an Erlang map.
+fn rows_to_maps<'a>(
+    env: Env<'a>,
+    columns: &[String],
+    rows: Vec<Vec<Term<'a>>>,
+) -> Result<Vec<Term<'a>>, DuckyError> {
+    use rustler::types::map::map_new;
 
-            Ok((column_names, raw_rows))
-        }
-        Err(_) => {
-            // Not a query, try executing as DDL/DML statement
-            stmt.execute(params)?;
-            Ok((Vec::new(), Vec::new()))
-        }
+    rows.into_iter()
+        .map(|row| {
+            columns
+                .iter()
+                .zip(row)
+                .try_fold(map_new(env), |map, (name, value)| {
+                    map.map_put(name.encode(env), value)
+                })
+                .map_err(|_| DuckyError::DatabaseError("Failed to build row map".to_string()))
+        })
+        .collect()
+}
+
+/// Maps an Arrow data type of a result column to its type atom.
+///
+/// DuckDB gives a projected `NULL` literal the type INTEGER, so it reports
+/// `integer` like any other INTEGER column. A column DuckDB left without a
+/// type reaches Arrow as `Null` and reports `null`.
+fn data_type_atom(data_type: &duckdb::arrow::datatypes::DataType) -> rustler::Atom {
+    use duckdb::arrow::datatypes::DataType;
+
+    match data_type {
+        DataType::Null => atoms::null(),
+        DataType::Boolean => atoms::boolean(),
+        DataType::Int8 => atoms::tiny_int(),
+        DataType::Int16 => atoms::small_int(),
+        DataType::Int32 => atoms::integer(),
+        DataType::Int64 => atoms::big_int(),
+        DataType::UInt8 => atoms::utiny_int(),
+        DataType::UInt16 => atoms::usmall_int(),
+        DataType::UInt32 => atoms::uinteger(),
+        DataType::UInt64 => atoms::ubig_int(),
+        DataType::Float32 => atoms::float(),
+        DataType::Float64 => atoms::double(),
+        DataType::Decimal128(_, _) | DataType::Decimal256(_, _) => atoms::decimal(),
+        DataType::Utf8 | DataType::LargeUtf8 | DataType::Utf8View => atoms::text(),
+        DataType::Binary
+        | DataType::LargeBinary
+        | DataType::BinaryView
+        | DataType::FixedSizeBinary(_) => atoms::blob(),
+        DataType::Timestamp(_, Some(_)) => atoms::timestamptz(),
+        DataType::Timestamp(_, None) => atoms::timestamp(),
+        DataType::Date32 | DataType::Date64 => atoms::date(),
+        DataType::Time32(_) | DataType::Time64(_) => atoms::time(),
+        DataType::Interval(_) | DataType::Duration(_) => atoms::interval(),
+        DataType::List(_) | DataType::LargeList(_) => atoms::list(),
+        DataType::FixedSizeList(_, _) => atoms::array(),
+        DataType::Struct(_) => atoms::struct_(),
+        DataType::Map(_, _) => atoms::map(),
+        DataType::Dictionary(_, _) => atoms::enum_(),
+        DataType::Union(_, _) => atoms::union(),
+        _ => atoms::unknown(),
     }
 }
 
 /// Converts an Erlang term to a DuckDB parameter.
 ///
-/// Supports basic types: Int, Float, String, Bool, Null
-fn term_to_duckdb_param(term: Term) -> Result<Box<dyn duckdb::types::ToSql>, DuckyError> {
-    use duckdb::types::Null;
+/// Supports basic types: Int, Float, String, Binary, Bool, Null
+///
+/// Atoms other than `null` and `nil`, which bind NULL, and `true` and
+/// `false`, which bind booleans, are bound as their name.
+///
+/// A `{null, Type}` tuple, where `Type` is a column type atom such as
+/// `integer`, binds a NULL of that type; see `bound_param`.
+///
+/// Strings are bound as VARCHAR, which DuckDB casts implicitly when the
+/// target is a UUID, so UUID-formatted strings can be bound directly.
+fn term_to_duckdb_param(term: Term) -> Result<Value, DuckyError> {
     use rustler::types::atom;
 
     // Try to decode as different types
     // Check for null/nil atoms first (Gleam's Nil maps to Erlang's nil atom)
     if let Ok(atom_val) = atom::Atom::from_term(term) {
         if atom_val == atoms::null() || atom_val == atoms::nil() {
-            return Ok(Box::new(Null));
+            return Ok(Value::Null);
+        }
+    }
+
+    // `{null, Type}` binds NULL, with the SQL cast by `bound_param`
+    if let Some(type_name) = typed_null(term) {
+        return type_name.map(|_| Value::Null);
+    }
+
+    // Temporal values use the same tagged tuples value_to_term produces
+    if let Ok((tag, value)) = term.decode::<(rustler::Atom, i64)>() {
+        use duckdb::types::TimeUnit;
+
+        if tag == atoms::timestamp() {
+            return Ok(Value::Timestamp(TimeUnit::Microsecond, value));
+        }
+        if tag == atoms::time() {
+            return Ok(Value::Time64(TimeUnit::Microsecond, value));
+        }
+        if tag == atoms::date() {
+            let days = i32::try_from(value)
+                .map_err(|_| DuckyError::DatabaseError(format!("Date out of range: {}", value)))?;
+            return Ok(Value::Date32(days));
+        }
+    }
+
+    // Exact decimals use the same `{decimal, unscaled, scale}` tuples
+    if let Ok((tag, unscaled, scale)) = term.decode::<(rustler::Atom, Term, i64)>() {
+        if tag == atoms::decimal() {
+            return decimal_to_param(unscaled, scale);
         }
     }
 
     if let Ok(b) = term.decode::<bool>() {
-        return Ok(Box::new(b));
+        return Ok(Value::Boolean(b));
+    }
+
+    // Any other atom binds as its name, for ENUM and VARCHAR columns
+    if term.is_atom() {
+        let name = term
+            .atom_to_string()
+            .map_err(|_| DuckyError::DatabaseError("Failed to read atom parameter".to_string()))?;
+        return Ok(Value::Text(name));
     }
 
     if let Ok(i) = term.decode::<i64>() {
-        return Ok(Box::new(i));
+        return Ok(Value::BigInt(i));
+    }
+
+    // Bignums beyond i64, such as large HUGEINT values read back from a query
+    if let Ok(i) = term.decode::<i128>() {
+        return Ok(Value::HugeInt(i));
     }
 
     if let Ok(f) = term.decode::<f64>() {
-        return Ok(Box::new(f));
+        return Ok(Value::Double(f));
     }
 
     if let Ok(s) = term.decode::<String>() {
-        return Ok(Box::new(s));
+        return Ok(Value::Text(s));
+    }
+
+    // Lists are bound as DuckDB LISTs, e.g. for `WHERE id = ANY(?)`
+    if term.is_list() {
+        return list_to_param(term);
+    }
+
+    if term.is_map() {
+        return map_to_param(term);
+    }
+
+    // Binaries that aren't valid UTF-8 are bound as BLOBs
+    if let Ok(bin) = term.decode::<rustler::Binary>() {
+        return Ok(Value::Blob(bin.as_slice().to_vec()));
     }
 
     Err(DuckyError::DatabaseError(
@@ -521,6 +6000,468 @@ fn term_to_duckdb_param(term: Term) -> Result<Box<dyn duckdb::types::ToSql>, Duc
     ))
 }
 
+/// Converts the parts of a `{decimal, unscaled, scale}` tuple into a
+/// DECIMAL value.
+///
+/// The unscaled value is an integer, or a string of digits when it doesn't
+/// fit in 64 bits. Like DuckDB's DECIMAL, it may have at most 38 digits and
+/// the scale may be at most 38. Values of up to 96 bits with a scale of up
+/// to 28 are bound as DECIMAL; wider ones are bound as their exact text,
+/// which DuckDB casts to the DECIMAL type the statement expects. Either
+/// way, DuckDB rejects values that overflow the target's precision.
+fn decimal_to_param(unscaled: Term, scale: i64) -> Result<Value, DuckyError> {
+    let invalid =
+        |reason: String| DuckyError::TypeMismatch(format!("Invalid decimal parameter: {}", reason));
+
+    let scale = u32::try_from(scale)
+        .ok()
+        .filter(|scale| *scale <= MAX_DECIMAL_DIGITS)
+        .ok_or_else(|| {
+            invalid(format!(
+                "scale {} is not between 0 and {}",
+                scale, MAX_DECIMAL_DIGITS
+            ))
+        })?;
+    let unscaled = match unscaled.decode::<i128>() {
+        Ok(i) => i,
+        Err(_) => unscaled
+            .decode::<String>()
+            .ok()
+            .and_then(|digits| digits.parse::<i128>().ok())
+            .ok_or_else(|| invalid("unscaled value must be an integer".to_string()))?,
+    };
+
+    let digits = unscaled.unsigned_abs().to_string();
+    if digits.len() > MAX_DECIMAL_DIGITS as usize {
+        return Err(invalid(format!(
+            "{} has more than {} digits",
+            unscaled, MAX_DECIMAL_DIGITS
+        )));
+    }
+
+    match rust_decimal::Decimal::try_from_i128_with_scale(unscaled, scale) {
+        Ok(decimal) => Ok(Value::Decimal(decimal)),
+        Err(_) => Ok(Value::Text(decimal_text(unscaled < 0, &digits, scale))),
+    }
+}
+
+/// Most digits a DuckDB DECIMAL can hold.
+const MAX_DECIMAL_DIGITS: u32 = 38;
+
+/// Formats an unscaled decimal, given as its sign and digits, as text such
+/// as `-0.05`.
+fn decimal_text(negative: bool, digits: &str, scale: u32) -> String {
+    let scale = scale as usize;
+    let padded = format!("{:0>width$}", digits, width = scale + 1);
+    let (whole, fraction) = padded.split_at(padded.len() - scale);
+    let sign = if negative { "-" } else { "" };
+    if fraction.is_empty() {
+        format!("{}{}", sign, whole)
+    } else {
+        format!("{}{}.{}", sign, whole, fraction)
+    }
+}
+
+/// Converts an Erlang list into a LIST value.
+///
+/// Numeric elements are promoted to the widest numeric type among them,
+/// as DuckDB does for a list literal: integers to HUGEINT when any needs
+/// it, integers to DECIMAL next to a decimal, and everything to DOUBLE
+/// next to a float. Every other non-null element must convert to the same
+/// DuckDB type as the first.
+fn list_to_param(term: Term) -> Result<Value, DuckyError> {
+    let elements = term
+        .decode::<Vec<Term>>()
+        .map_err(|_| DuckyError::DatabaseError("Improper list parameter".to_string()))?;
+    let values = elements
+        .into_iter()
+        .map(term_to_duckdb_param)
+        .collect::<Result<Vec<Value>, DuckyError>>()?;
+    let values = promote_numbers(values);
+
+    let mut non_null = values.iter().filter(|value| !matches!(value, Value::Null));
+    if let Some(first) = non_null.next() {
+        let element_type = std::mem::discriminant(first);
+        if non_null.any(|value| std::mem::discriminant(value) != element_type) {
+            return Err(DuckyError::TypeMismatch(
+                "List parameter mixes incompatible element types".to_string(),
+            ));
+        }
+    }
+
+    Ok(Value::List(values))
+}
+
+/// Converts the numeric values of a list to their widest type, leaving
+/// the list alone if it holds anything but numbers and NULLs.
+fn promote_numbers(values: Vec<Value>) -> Vec<Value> {
+    use rust_decimal::prelude::ToPrimitive;
+
+    // Ordered from narrowest to widest
+    let rank = |value: &Value| match value {
+        Value::Null => Some(0),
+        Value::BigInt(_) => Some(1),
+        Value::HugeInt(_) => Some(2),
+        Value::Decimal(_) => Some(3),
+        Value::Double(_) => Some(4),
+        _ => None,
+    };
+    let widest = match values.iter().map(rank).collect::<Option<Vec<_>>>() {
+        Some(ranks) => ranks.into_iter().max().unwrap_or(0),
+        None => return values,
+    };
+
+    let as_decimal = |value: &Value| match value {
+        Value::BigInt(i) => Some(rust_decimal::Decimal::from(*i)),
+        Value::HugeInt(i) => rust_decimal::Decimal::try_from_i128_with_scale(*i, 0).ok(),
+        Value::Decimal(d) => Some(*d),
+        _ => None,
+    };
+    // Integers too wide for a decimal fall back to DOUBLE, like DuckDB does
+    let widest = if widest == 3
+        && values
+            .iter()
+            .any(|value| !matches!(value, Value::Null) && as_decimal(value).is_none())
+    {
+        4
+    } else {
+        widest
+    };
+
+    values
+        .into_iter()
+        .map(|value| match (widest, value) {
+            (_, Value::Null) => Value::Null,
+            (2, Value::BigInt(i)) => Value::HugeInt(i128::from(i)),
+            (3, value) => as_decimal(&value).map_or(value, Value::Decimal),
+            (4, Value::BigInt(i)) => Value::Double(i as f64),
+            (4, Value::HugeInt(i)) => Value::Double(i as f64),
+            (4, Value::Decimal(d)) => Value::Double(d.to_f64().unwrap_or(f64::NAN)),
+            (_, value) => value,
+        })
+        .collect()
+}
+
+/// Converts an Erlang map into a STRUCT value.
+///
+/// Keys may be strings or atoms and become the field names. Maps have no
+/// order, so fields are sorted by name; inserting into a STRUCT column
+/// casts the value field by field by name.
+fn map_to_param(term: Term) -> Result<Value, DuckyError> {
+    use rustler::types::map::MapIterator;
+
+    let invalid = |msg: &str| DuckyError::DatabaseError(msg.to_string());
+    let entries = MapIterator::new(term).ok_or_else(|| invalid("Invalid map parameter"))?;
+
+    let mut fields = Vec::new();
+    for (key, value) in entries {
+        let name = if key.is_atom() {
+            key.atom_to_string()
+        } else {
+            key.decode::<String>()
+        }
+        .map_err(|_| invalid("Struct field names must be strings or atoms"))?;
+        fields.push((name, term_to_duckdb_param(value)?));
+    }
+
+    if fields.is_empty() {
+        return Err(invalid("Empty map can't be bound as a STRUCT"));
+    }
+    fields.sort_by(|a, b| a.0.cmp(&b.0));
+    if fields.windows(2).any(|pair| pair[0].0 == pair[1].0) {
+        return Err(invalid("Struct parameter has duplicate field names"));
+    }
+
+    Ok(Value::Struct(duckdb::types::OrderedMap::from(fields)))
+}
+
+/// Converts a list of Erlang terms to the values appended as a row.
+///
+/// The appender casts each value to its column's type, so values are
+/// appended as `bound_param` binds them.
+///
+/// Conversion errors name the value by its 1-based position.
+fn terms_to_params(terms: Vec<Term>) -> Result<Vec<Value>, DuckyError> {
+    terms
+        .into_iter()
+        .enumerate()
+        .map(|(i, term)| {
+            bound_param(term)
+                .map(|(value, _)| value)
+                .map_err(|e| e.for_parameter(&(i + 1).to_string()))
+        })
+        .collect()
+}
+
+/// Converts positional parameters to the values bound for them, casting
+/// the placeholders of any bound as something other than their type; see
+/// `bound_param`.
+///
+/// Conversion errors name the parameter by its 1-based position.
+fn bind_params<'s>(
+    sql: &'s str,
+    terms: Vec<Term>,
+) -> Result<(Cow<'s, str>, Vec<Value>), DuckyError> {
+    let mut casts = HashMap::new();
+    let mut params = Vec::with_capacity(terms.len());
+    for (i, term) in terms.into_iter().enumerate() {
+        let key = (i + 1).to_string();
+        let (param, sql_type) = bound_param(term).map_err(|e| e.for_parameter(&key))?;
+        if let Some(sql_type) = sql_type {
+            casts.insert(key, sql_type);
+        }
+        params.push(param);
+    }
+    Ok((cast_placeholders(sql, &casts), params))
+}
+
+/// Converts an Erlang term to the value bound for it, along with the SQL
+/// type its placeholder must be cast to, if any.
+///
+/// DuckDB can't bind a NULL of a given type, so `{null, integer}` binds a
+/// plain NULL cast to INTEGER. The duckdb crate can't bind dates, times,
+/// decimals, lists or structs, so these are bound as text in the form
+/// DuckDB's casts from VARCHAR read, and cast back to their type.
+fn bound_param(term: Term) -> Result<(Value, Option<String>), DuckyError> {
+    if let Some(type_name) = typed_null(term) {
+        return Ok((Value::Null, Some(type_name?.to_string())));
+    }
+
+    match term_to_duckdb_param(term)? {
+        value @ (Value::Date32(_)
+        | Value::Time64(..)
+        | Value::Decimal(_)
+        | Value::List(_)
+        | Value::Struct(_)) => Ok((Value::Text(value_text(&value)), Some(sql_type(&value)))),
+        value => Ok((value, None)),
+    }
+}
+
+/// Names the SQL type of a converted value.
+///
+/// Decimals take the widest precision, so any value of their scale fits.
+/// Lists take the type of their first element whose type is known, or the
+/// widest scale among decimal elements, and NULLs of unknown type are
+/// typed as `"NULL"`, which DuckDB casts to any other type.
+fn sql_type(value: &Value) -> String {
+    match value {
+        Value::Boolean(_) => "BOOLEAN".to_string(),
+        Value::BigInt(_) => "BIGINT".to_string(),
+        Value::HugeInt(_) => "HUGEINT".to_string(),
+        Value::Double(_) => "DOUBLE".to_string(),
+        Value::Decimal(d) => format!("DECIMAL({}, {})", MAX_DECIMAL_DIGITS, d.scale()),
+        Value::Text(_) => "VARCHAR".to_string(),
+        Value::Blob(_) => "BLOB".to_string(),
+        Value::Timestamp(..) => "TIMESTAMP".to_string(),
+        Value::Date32(_) => "DATE".to_string(),
+        Value::Time64(..) => "TIME".to_string(),
+        Value::List(values) => {
+            let scale = values
+                .iter()
+                .filter_map(|value| match value {
+                    Value::Decimal(d) => Some(d.scale()),
+                    _ => None,
+                })
+                .max();
+            let element = match scale {
+                Some(scale) => format!("DECIMAL({}, {})", MAX_DECIMAL_DIGITS, scale),
+                None => values
+                    .iter()
+                    .map(sql_type)
+                    .find(|element| !element.starts_with(UNKNOWN_TYPE))
+                    .or_else(|| values.first().map(sql_type))
+                    .unwrap_or_else(|| UNKNOWN_TYPE.to_string()),
+            };
+            format!("{}[]", element)
+        }
+        Value::Struct(fields) => {
+            let fields = fields
+                .iter()
+                .map(|(name, value)| format!("{} {}", quote_identifier(name), sql_type(value)))
+                .collect::<Vec<_>>();
+            format!("STRUCT({})", fields.join(", "))
+        }
+        _ => UNKNOWN_TYPE.to_string(),
+    }
+}
+
+/// The type `sql_type` gives NULLs.
+const UNKNOWN_TYPE: &str = "\"NULL\"";
+
+/// Formats a converted value as the text DuckDB casts back to it.
+///
+/// List elements and struct fields are quoted, with quotes and
+/// backslashes escaped, so DuckDB reads them back whatever they contain.
+fn value_text(value: &Value) -> String {
+    match value {
+        Value::Null => "NULL".to_string(),
+        Value::Boolean(b) => b.to_string(),
+        Value::BigInt(i) => i.to_string(),
+        Value::HugeInt(i) => i.to_string(),
+        // Debug formatting keeps exponents, so huge and tiny floats stay short
+        Value::Double(f) => format!("{:?}", f),
+        Value::Decimal(d) => d.to_string(),
+        Value::Text(s) => s.clone(),
+        Value::Blob(bytes) => bytes
+            .iter()
+            .map(|&b| match b {
+                b' '..=b'~' if b != b'\\' => char::from(b).to_string(),
+                _ => format!("\\x{:02X}", b),
+            })
+            .collect(),
+        Value::Timestamp(_, micros) => format!(
+            "{} {}",
+            date_text(micros.div_euclid(MICROS_PER_DAY)),
+            time_text(micros.rem_euclid(MICROS_PER_DAY))
+        ),
+        Value::Date32(days) => date_text(i64::from(*days)),
+        Value::Time64(_, micros) => time_text(*micros),
+        Value::List(values) => {
+            let elements = values.iter().map(nested_text).collect::<Vec<_>>();
+            format!("[{}]", elements.join(", "))
+        }
+        Value::Struct(fields) => {
+            let fields = fields
+                .iter()
+                .map(|(name, value)| format!("{}: {}", quote_nested(name), nested_text(value)))
+                .collect::<Vec<_>>();
+            format!("{{{}}}", fields.join(", "))
+        }
+        other => format!("{:?}", other),
+    }
+}
+
+/// Formats a list element or struct field for `value_text`.
+fn nested_text(value: &Value) -> String {
+    match value {
+        Value::Null => "NULL".to_string(),
+        Value::List(_) | Value::Struct(_) => value_text(value),
+        _ => quote_nested(&value_text(value)),
+    }
+}
+
+/// Quotes text inside a list or struct, escaping quotes and backslashes.
+fn quote_nested(text: &str) -> String {
+    format!("\"{}\"", text.replace('\\', "\\\\").replace('"', "\\\""))
+}
+
+const MICROS_PER_DAY: i64 = 86_400_000_000;
+
+/// Formats days since 1970-01-01 as a date such as `2024-01-15`, marking
+/// years before 1 AD as `(BC)` like DuckDB does.
+fn date_text(days: i64) -> String {
+    // Converts to a proleptic Gregorian date, counting years from March so
+    // leap days fall at the end of the year
+    let days = days + 719_468;
+    let era = days.div_euclid(146_097);
+    let day_of_era = days.rem_euclid(146_097);
+    let year_of_era =
+        (day_of_era - day_of_era / 1_460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let month_from_march = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * month_from_march + 2) / 5 + 1;
+    let month = if month_from_march < 10 {
+        month_from_march + 3
+    } else {
+        month_from_march - 9
+    };
+    let year = year_of_era + era * 400 + i64::from(month <= 2);
+
+    if year > 0 {
+        format!("{:04}-{:02}-{:02}", year, month, day)
+    } else {
+        format!("{:04}-{:02}-{:02} (BC)", 1 - year, month, day)
+    }
+}
+
+/// Formats microseconds since midnight as a time such as `14:30:45.000000`.
+fn time_text(micros: i64) -> String {
+    format!(
+        "{:02}:{:02}:{:02}.{:06}",
+        micros / 3_600_000_000,
+        micros / 60_000_000 % 60,
+        micros / 1_000_000 % 60,
+        micros % 1_000_000
+    )
+}
+
+/// Reads the SQL type of a `{null, Type}` typed NULL.
+///
+/// `Type` is an atom or string naming a column type, as reported by
+/// `execute_query_typed`. Returns `None` for any other term.
+fn typed_null(term: Term) -> Option<Result<&'static str, DuckyError>> {
+    let (tag, data_type) = term.decode::<(rustler::Atom, Term)>().ok()?;
+    if tag != atoms::null() {
+        return None;
+    }
+
+    let name = if data_type.is_atom() {
+        data_type.atom_to_string().ok()
+    } else {
+        data_type.decode::<String>().ok()
+    };
+    Some(name.as_deref().and_then(sql_type_name).ok_or_else(|| {
+        DuckyError::TypeMismatch(format!("Unknown type for a typed NULL: {:?}", data_type))
+    }))
+}
+
+/// Maps a column type atom's name to the SQL type it stands for.
+fn sql_type_name(name: &str) -> Option<&'static str> {
+    let sql_type = match name {
+        "boolean" => "BOOLEAN",
+        "tiny_int" => "TINYINT",
+        "small_int" => "SMALLINT",
+        "integer" => "INTEGER",
+        "big_int" => "BIGINT",
+        "utiny_int" => "UTINYINT",
+        "usmall_int" => "USMALLINT",
+        "uinteger" => "UINTEGER",
+        "ubig_int" => "UBIGINT",
+        "float" => "FLOAT",
+        "double" => "DOUBLE",
+        "decimal" => "DECIMAL",
+        "text" => "VARCHAR",
+        "blob" => "BLOB",
+        "timestamp" => "TIMESTAMP",
+        "timestamptz" => "TIMESTAMPTZ",
+        "date" => "DATE",
+        "time" => "TIME",
+        "interval" => "INTERVAL",
+        _ => return None,
+    };
+    Some(sql_type)
+}
+
+/// Borrows converted parameters in the form expected for binding.
+fn param_refs(params: &[Value]) -> Vec<&dyn ToSql> {
+    params.iter().map(|p| p as &dyn ToSql).collect()
+}
+
+/// Checks that the NIF can open DuckDB and run a query, unlike `test`,
+/// which only shows the library loaded.
+///
+/// Opens a throwaway in-memory database, so it doesn't need a connection.
+///
+/// ## Returns
+/// - `Ok(version)` with the linked DuckDB version, such as `"v1.4.3"`
+/// - `Err(DuckyError)` if DuckDB can't open a database or runs the query
+///   wrongly
+#[rustler::nif(schedule = "DirtyCpu")]
+fn self_test() -> Result<String, DuckyError> {
+    let connection = DuckDBConnection::open_in_memory()?;
+    let (answer, version): (i32, String) =
+        connection.query_row("SELECT 42, version()", [], |row| {
+            Ok((row.get(0)?, row.get(1)?))
+        })?;
+    if answer != 42 {
+        return Err(DuckyError::DatabaseError(format!(
+            "Self test expected 42, got {}",
+            answer
+        )));
+    }
+    Ok(version)
+}
+
 /// Health check NIF to verify the library loads correctly.
 #[rustler::nif]
 fn test() -> String {
@@ -531,9 +6472,312 @@ fn test() -> String {
 fn on_load(env: Env, _: Term) -> bool {
     #[allow(non_local_definitions)]
     {
-        let _ = rustler::resource!(ConnectionResource, env);
+        let _ = rustler::resource!(AppenderResource, env);
+        let _ = rustler::resource!(PreparedStatementResource, env);
+        let _ = rustler::resource!(CursorResource, env);
+        let _ = rustler::resource!(PoolResource, env);
+        let _ = rustler::resource!(DatabaseResource, env);
+        let _ = rustler::resource!(ScalarCallResource, env);
     }
     true
 }
 
 rustler::init!("ducky_nif", load = on_load);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::panic::{self, AssertUnwindSafe};
+
+    #[test]
+    fn lock_recovers_poisoned_connection() {
+        let conn = ConnectionResource::new(DuckDBConnection::open_in_memory().unwrap());
+
+        // Simulate a conversion panic while a NIF holds the lock
+        let result = panic::catch_unwind(AssertUnwindSafe(|| {
+            let _connection = conn.lock().unwrap();
+            panic!("conversion failed");
+        }));
+        assert!(result.is_err());
+        assert!(conn.connection.is_poisoned());
+
+        let connection = conn.lock().unwrap();
+        let value: i64 = connection
+            .query_row("SELECT 42", [], |row| row.get(0))
+            .unwrap();
+        assert_eq!(value, 42);
+    }
+
+    #[test]
+    fn interrupt_timer_stops_query_at_deadline() {
+        let connection = DuckDBConnection::open_in_memory().unwrap();
+        let timer = InterruptTimer::shared();
+
+        let deadline = timer.schedule(Duration::from_millis(50), connection.interrupt_handle());
+        let result: Result<i64, _> = connection.query_row(
+            "SELECT count(*) FROM range(1000000000000) t(i) WHERE i % 7 = 3",
+            [],
+            |row| row.get(0),
+        );
+        assert!(timer.cancel(deadline.unwrap()));
+        assert!(DuckyError::from(result.unwrap_err()).is_interrupt());
+
+        // A cancelled deadline never fires
+        let deadline = timer.schedule(Duration::from_secs(3600), connection.interrupt_handle());
+        assert!(!timer.cancel(deadline.unwrap()));
+        let value: i64 = connection
+            .query_row("SELECT 42", [], |row| row.get(0))
+            .unwrap();
+        assert_eq!(value, 42);
+    }
+
+    #[test]
+    fn transaction_open_sees_begin_statements() {
+        let connection = DuckDBConnection::open_in_memory().unwrap();
+        assert!(!transaction_open(&connection).unwrap());
+
+        connection.execute_batch("BEGIN").unwrap();
+        assert!(transaction_open(&connection).unwrap());
+
+        connection.execute_batch("ROLLBACK").unwrap();
+        assert!(!transaction_open(&connection).unwrap());
+    }
+
+    #[test]
+    fn cursor_connections_keep_the_search_path() {
+        let connection = DuckDBConnection::open_in_memory().unwrap();
+        connection
+            .execute_batch("CREATE SCHEMA s; CREATE TABLE s.t AS SELECT 1 AS x; SET schema = 's'")
+            .unwrap();
+
+        let cursor_connection = cursor_connection(&connection).unwrap();
+        let x: i32 = cursor_connection
+            .query_row("SELECT x FROM t", [], |row| row.get(0))
+            .unwrap();
+        assert_eq!(x, 1);
+    }
+
+    #[test]
+    fn split_statements_skips_dollar_quoted_semicolons() {
+        assert_eq!(
+            split_statements("SELECT $$a;b$$; SELECT 2"),
+            vec!["SELECT $$a;b$$", " SELECT 2"]
+        );
+        assert_eq!(
+            split_statements("SELECT $fn$ $$; $fn$; SELECT $1"),
+            vec!["SELECT $fn$ $$; $fn$", " SELECT $1"]
+        );
+        assert_eq!(
+            split_statements("SELECT $1; SELECT $name"),
+            vec!["SELECT $1", " SELECT $name"]
+        );
+    }
+
+    #[test]
+    fn cast_placeholders_wraps_listed_parameters() {
+        let casts = HashMap::from([("2".to_string(), "INTEGER")]);
+        assert_eq!(
+            cast_placeholders("SELECT ?, COALESCE(?, 0) -- ?", &casts),
+            "SELECT ?, COALESCE(CAST(? AS INTEGER), 0) -- ?"
+        );
+        assert_eq!(
+            cast_placeholders("SELECT '?', $1, $2, ?2", &casts),
+            "SELECT '?', $1, CAST($2 AS INTEGER), CAST(?2 AS INTEGER)"
+        );
+
+        let casts = HashMap::from([("id".to_string(), "BIGINT")]);
+        assert_eq!(
+            cast_placeholders("SELECT $id, $ids, a$id, $$ $id $$", &casts),
+            "SELECT CAST($id AS BIGINT), $ids, a$id, $$ $id $$"
+        );
+    }
+
+    #[test]
+    fn cast_null_parameters_take_their_type() {
+        let connection = DuckDBConnection::open_in_memory().unwrap();
+        let casts = HashMap::from([("1".to_string(), "INTEGER")]);
+        let sql = cast_placeholders("SELECT typeof(?), COALESCE(?, 7)", &casts);
+        let (data_type, fallback): (String, i64) = connection
+            .query_row(&sql, [Value::Null, Value::Null], |row| {
+                Ok((row.get(0)?, row.get(1)?))
+            })
+            .unwrap();
+        assert_eq!(data_type, "INTEGER");
+        assert_eq!(fallback, 7);
+    }
+
+    #[test]
+    fn temp_files_get_fresh_names_and_are_removed() {
+        let first = TempFile::create("csv", b"a,b\n").unwrap();
+        let second = TempFile::create("csv", b"c,d\n").unwrap();
+        assert_ne!(first.path, second.path);
+        assert_eq!(std::fs::read(&first.path).unwrap(), b"a,b\n");
+
+        let path = first.path.clone();
+        drop(first);
+        assert!(!path.exists());
+    }
+
+    #[test]
+    fn positional_names_replace_empty_and_repeated_columns() {
+        let columns = ["a", "", "a", "b"].map(String::from).to_vec();
+        assert_eq!(
+            positional_fallback_names(columns),
+            ["a", "column_1", "column_2", "b"]
+        );
+    }
+
+    #[test]
+    fn bits_skip_leading_padding() {
+        // '101'::BIT is stored with five padding bits set to 1
+        assert_eq!(bits_to_string(&[5, 0b1111_1101]), "101");
+        assert_eq!(
+            bits_to_string(&[0, 0b1000_0001, 0b0000_0010]),
+            "1000000100000010"
+        );
+    }
+
+    #[test]
+    fn byte_sizes_parse_both_unit_systems() {
+        assert_eq!(parse_byte_size("0 bytes"), Some(0));
+        assert_eq!(parse_byte_size("1 byte"), Some(1));
+        assert_eq!(parse_byte_size("1.5 MiB"), Some(1_572_864));
+        assert_eq!(parse_byte_size("2.0 GB"), Some(2_000_000_000));
+        assert_eq!(parse_byte_size("Unlimited"), None);
+    }
+
+    #[test]
+    fn identifiers_are_double_quoted() {
+        assert_eq!(quote_identifier("users"), "\"users\"");
+        assert_eq!(quote_identifier("select"), "\"select\"");
+        assert_eq!(quote_identifier("first name"), "\"first name\"");
+        assert_eq!(quote_identifier("say \"hi\""), "\"say \"\"hi\"\"\"");
+
+        let connection = DuckDBConnection::open_in_memory().unwrap();
+        let table = quote_identifier("select");
+        let column = quote_identifier("say \"hi\"");
+        connection
+            .execute_batch(&format!("CREATE TABLE {} ({} INTEGER)", table, column))
+            .unwrap();
+        let columns: i64 = connection
+            .query_row(
+                "SELECT count(*) FROM pragma_table_info('select') WHERE name = 'say \"hi\"'",
+                [],
+                |row| row.get(0),
+            )
+            .unwrap();
+        assert_eq!(columns, 1);
+    }
+
+    #[test]
+    fn list_numbers_promote_to_the_widest_type() {
+        let promoted = promote_numbers(vec![Value::BigInt(1), Value::Null, Value::Double(2.5)]);
+        assert_eq!(
+            promoted,
+            vec![Value::Double(1.0), Value::Null, Value::Double(2.5)]
+        );
+
+        let big = i128::from(i64::MAX) + 1;
+        let promoted = promote_numbers(vec![Value::BigInt(1), Value::HugeInt(big)]);
+        assert_eq!(promoted, vec![Value::HugeInt(1), Value::HugeInt(big)]);
+
+        let mixed = vec![Value::BigInt(1), Value::Text("a".to_string())];
+        assert_eq!(promote_numbers(mixed.clone()), mixed);
+    }
+
+    #[test]
+    fn decimal_text_places_the_point() {
+        assert_eq!(decimal_text(false, "12345", 2), "123.45");
+        assert_eq!(decimal_text(true, "5", 2), "-0.05");
+        assert_eq!(decimal_text(false, "42", 0), "42");
+    }
+
+    #[test]
+    fn dates_and_times_format_like_duckdb() {
+        assert_eq!(date_text(0), "1970-01-01");
+        assert_eq!(date_text(19_737), "2024-01-15");
+        assert_eq!(date_text(-719_528), "0001-01-01 (BC)");
+        assert_eq!(time_text(52_245_000_001), "14:30:45.000001");
+    }
+
+    #[test]
+    fn unbindable_values_cast_back_from_text() {
+        use duckdb::types::{OrderedMap, TimeUnit};
+
+        let connection = DuckDBConnection::open_in_memory().unwrap();
+        let round_trips = |value: Value, literal: &str| -> bool {
+            let sql = format!(
+                "SELECT CAST(? AS {}) IS NOT DISTINCT FROM {}",
+                sql_type(&value),
+                literal
+            );
+            connection
+                .query_row(&sql, [Value::Text(value_text(&value))], |row| row.get(0))
+                .unwrap()
+        };
+
+        let decimal = rust_decimal::Decimal::new(-5, 2);
+        assert!(round_trips(Value::Decimal(decimal), "-0.05"));
+        assert!(round_trips(
+            Value::Date32(-719_528),
+            "DATE '0001-01-01 (BC)'"
+        ));
+        assert!(round_trips(
+            Value::Time64(TimeUnit::Microsecond, 52_245_000_001),
+            "TIME '14:30:45.000001'"
+        ));
+
+        let tagged = Value::Struct(OrderedMap::from(vec![
+            ("at".to_string(), Value::Date32(19_737)),
+            ("bytes".to_string(), Value::Blob(vec![0, b'\\', b'a'])),
+            (
+                "name".to_string(),
+                Value::Text("say \"hi\", [NULL]".to_string()),
+            ),
+        ]));
+        assert!(round_trips(
+            Value::List(vec![tagged, Value::Null]),
+            "[{'at': DATE '2024-01-15', 'bytes': '\\x00\\x5Ca'::BLOB, \
+             'name': 'say \"hi\", [NULL]'}, NULL]"
+        ));
+        assert!(round_trips(
+            Value::List(vec![
+                Value::List(vec![]),
+                Value::List(vec![Value::Double(1e300)])
+            ]),
+            "[[], [1e300]]"
+        ));
+        assert!(round_trips(Value::List(vec![Value::Null]), "[NULL]"));
+    }
+
+    #[test]
+    fn result_hashes_ignore_batch_boundaries() {
+        let connection = DuckDBConnection::open_in_memory().unwrap();
+        let hash = |sql: &str, split: Option<usize>| {
+            let mut stmt = connection.prepare(sql).unwrap();
+            let arrow = stmt.query_arrow([]).unwrap();
+            let mut hasher = ResultHasher::new(&arrow.get_schema());
+            for batch in arrow {
+                match split {
+                    Some(at) => {
+                        hasher.add_batch(&batch.slice(0, at)).unwrap();
+                        hasher
+                            .add_batch(&batch.slice(at, batch.num_rows() - at))
+                            .unwrap();
+                    }
+                    None => hasher.add_batch(&batch).unwrap(),
+                }
+            }
+            hasher.finish()
+        };
+
+        let sql = "SELECT i, [i, i + 1] AS l, {'s': i::VARCHAR} AS s FROM range(10) t(i)";
+        assert_eq!(hash(sql, None), hash(sql, Some(3)));
+        assert_eq!(hash(sql, None).len(), 64);
+        assert_ne!(
+            hash("SELECT ['a, b'] AS l", None),
+            hash("SELECT ['a', 'b'] AS l", None)
+        );
+        assert_ne!(hash("SELECT 1 AS a", None), hash("SELECT 1 AS b", None));
+    }
+}
@@ -0,0 +1,55 @@
+-module(ducky_ffi).
+-export([start_scalar_handler/1, start_query_logger/1]).
+
+%% Spawns a process that answers calls of a scalar function registered with
+%% ducky_nif:register_scalar_function/3 by calling Fun, until the process
+%% that started it exits.
+start_scalar_handler(Fun) ->
+    Owner = self(),
+    spawn(fun() ->
+        Ref = erlang:monitor(process, Owner),
+        scalar_handler_loop(Fun, Ref)
+    end).
+
+scalar_handler_loop(Fun, Ref) ->
+    receive
+        {ducky_scalar_call, _Name, Call, Args} ->
+            ducky_nif:scalar_function_reply(Call, scalar_call(Fun, Args)),
+            scalar_handler_loop(Fun, Ref);
+        {'DOWN', Ref, process, _Pid, _Reason} ->
+            ok
+    end.
+
+%% Calls Fun with the arguments as Gleam options and converts its
+%% Result(Option(String), String) into a reply. A crash fails the query
+%% rather than the handler.
+scalar_call(Fun, Args) ->
+    Options = [case Arg of null -> none; Value -> {some, Value} end
+               || Arg <- Args],
+    try Fun(Options) of
+        {ok, {some, Value}} -> Value;
+        {ok, none} -> null;
+        {error, Message} -> {error, Message}
+    catch
+        Class:Reason ->
+            {error, iolist_to_binary(io_lib:format("~p: ~p", [Class, Reason]))}
+    end.
+
+%% Spawns a process that calls Fun(Sql, DurationMicros) for every query_log
+%% message sent by ducky_nif:enable_query_log/2, until the process that
+%% started it exits.
+start_query_logger(Fun) ->
+    Owner = self(),
+    spawn(fun() ->
+        Ref = erlang:monitor(process, Owner),
+        query_logger_loop(Fun, Ref)
+    end).
+
+query_logger_loop(Fun, Ref) ->
+    receive
+        {query_log, Sql, Micros} ->
+            catch Fun(Sql, Micros),
+            query_logger_loop(Fun, Ref);
+        {'DOWN', Ref, process, _Pid, _Reason} ->
+            ok
+    end.
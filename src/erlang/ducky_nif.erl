@@ -1,5 +1,36 @@
 -module(ducky_nif).
--export([connect/1, close/1, execute_query/3, test/0]).
+-export([connect/1, connect_with_config/2, connect_with_extensions/2,
+         open_database/1, database_connect/1,
+         load_extension/2, set_s3_credentials/5, attach/4, detach/2,
+         create_schema/2, set_schema/2,
+         get_setting/2, set_setting/3, set_threads/2, get_threads/1,
+         close/1, interrupt/1, ping/1, enable_query_log/2, disable_query_log/1,
+         begin_transaction/1, begin_read_only/1, commit/1, rollback/1,
+         reset/1, in_transaction/1,
+         with_transaction/2,
+         checkpoint/1, force_checkpoint/1, analyze/1, vacuum/1,
+         database_size/1,
+         execute_query/3, execute_query_timeout/4, execute_query_named/3,
+         execute_query_opts/4, execute_query_timed/4,
+         execute_query_limited/4, execute_query_typed/3,
+         execute_query_maps/3, execute_query_columnar/3, query_scalar/3,
+         query_one/3, prepare/2, execute_prepared/2, set_statement_cache_size/2,
+         clear_statement_cache/1, execute_dml/3, create_temp_table_as/4,
+         execute_many/3,
+         execute_batch/2, execute_multi/2, validate_sql/2, explain/3,
+         explain_analyze/3, describe/2, table_info/2, list_schemas/1,
+         list_tables/2, table_exists/3, describe_database/1, list_settings/1,
+         currval/2, nextval/2,
+         register_table/3, register_scalar_function/3,
+         scalar_function_reply/2,
+         import_parquet/4, import_csv/4, import_json/4,
+         copy_from_iodata/4, export_parquet/5, export_csv/5,
+         query_arrow_ipc/3, query_arrow_batches/4, query_hash/3,
+         query_ndjson/3,
+         pool_open/2, pool_execute/3,
+         appender_create/2, appender_append_row/2, appender_flush/1,
+         query_cursor/3, cursor_fetch/2, cell_blob_chunk/5, cursor_close/1,
+         quote_identifier/1, self_test/0, test/0]).
 -on_load(init/0).
 
 init() ->
@@ -19,11 +50,266 @@ init() ->
 connect(_Path) ->
     erlang:nif_error(nif_not_loaded).
 
+connect_with_config(_Path, _Config) ->
+    erlang:nif_error(nif_not_loaded).
+
+connect_with_extensions(_Path, _Extensions) ->
+    erlang:nif_error(nif_not_loaded).
+
+open_database(_Path) ->
+    erlang:nif_error(nif_not_loaded).
+
+database_connect(_Database) ->
+    erlang:nif_error(nif_not_loaded).
+
+load_extension(_Connection, _Name) ->
+    erlang:nif_error(nif_not_loaded).
+
+set_s3_credentials(_Connection, _KeyId, _Secret, _Region, _Endpoint) ->
+    erlang:nif_error(nif_not_loaded).
+
+attach(_Connection, _Path, _Alias, _Options) ->
+    erlang:nif_error(nif_not_loaded).
+
+detach(_Connection, _Alias) ->
+    erlang:nif_error(nif_not_loaded).
+
+create_schema(_Connection, _Name) ->
+    erlang:nif_error(nif_not_loaded).
+
+set_schema(_Connection, _Name) ->
+    erlang:nif_error(nif_not_loaded).
+
+get_setting(_Connection, _Name) ->
+    erlang:nif_error(nif_not_loaded).
+
+set_setting(_Connection, _Name, _Value) ->
+    erlang:nif_error(nif_not_loaded).
+
+set_threads(_Connection, _Threads) ->
+    erlang:nif_error(nif_not_loaded).
+
+get_threads(_Connection) ->
+    erlang:nif_error(nif_not_loaded).
+
 close(_Connection) ->
     erlang:nif_error(nif_not_loaded).
 
+interrupt(_Connection) ->
+    erlang:nif_error(nif_not_loaded).
+
+ping(_Connection) ->
+    erlang:nif_error(nif_not_loaded).
+
+enable_query_log(_Connection, _Pid) ->
+    erlang:nif_error(nif_not_loaded).
+
+disable_query_log(_Connection) ->
+    erlang:nif_error(nif_not_loaded).
+
+begin_transaction(_Connection) ->
+    erlang:nif_error(nif_not_loaded).
+
+begin_read_only(_Connection) ->
+    erlang:nif_error(nif_not_loaded).
+
+commit(_Connection) ->
+    erlang:nif_error(nif_not_loaded).
+
+rollback(_Connection) ->
+    erlang:nif_error(nif_not_loaded).
+
+reset(_Connection) ->
+    erlang:nif_error(nif_not_loaded).
+
+in_transaction(_Connection) ->
+    erlang:nif_error(nif_not_loaded).
+
+with_transaction(_Connection, _Statements) ->
+    erlang:nif_error(nif_not_loaded).
+
+checkpoint(_Connection) ->
+    erlang:nif_error(nif_not_loaded).
+
+force_checkpoint(_Connection) ->
+    erlang:nif_error(nif_not_loaded).
+
+analyze(_Connection) ->
+    erlang:nif_error(nif_not_loaded).
+
+vacuum(_Connection) ->
+    erlang:nif_error(nif_not_loaded).
+
+database_size(_Connection) ->
+    erlang:nif_error(nif_not_loaded).
+
 execute_query(_Connection, _Sql, _Params) ->
     erlang:nif_error(nif_not_loaded).
 
+execute_query_opts(_Connection, _Sql, _Params, _Options) ->
+    erlang:nif_error(nif_not_loaded).
+
+execute_query_timed(_Connection, _Sql, _Params, _Options) ->
+    erlang:nif_error(nif_not_loaded).
+
+execute_query_limited(_Connection, _Sql, _Params, _MaxRows) ->
+    erlang:nif_error(nif_not_loaded).
+
+execute_query_timeout(_Connection, _Sql, _Params, _TimeoutMs) ->
+    erlang:nif_error(nif_not_loaded).
+
+execute_query_named(_Connection, _Sql, _Params) ->
+    erlang:nif_error(nif_not_loaded).
+
+execute_query_typed(_Connection, _Sql, _Params) ->
+    erlang:nif_error(nif_not_loaded).
+
+execute_query_maps(_Connection, _Sql, _Params) ->
+    erlang:nif_error(nif_not_loaded).
+
+query_scalar(_Connection, _Sql, _Params) ->
+    erlang:nif_error(nif_not_loaded).
+
+query_one(_Connection, _Sql, _Params) ->
+    erlang:nif_error(nif_not_loaded).
+
+prepare(_Connection, _Sql) ->
+    erlang:nif_error(nif_not_loaded).
+
+execute_prepared(_Statement, _Params) ->
+    erlang:nif_error(nif_not_loaded).
+
+set_statement_cache_size(_Connection, _Size) ->
+    erlang:nif_error(nif_not_loaded).
+
+clear_statement_cache(_Connection) ->
+    erlang:nif_error(nif_not_loaded).
+
+execute_dml(_Connection, _Sql, _Params) ->
+    erlang:nif_error(nif_not_loaded).
+
+create_temp_table_as(_Connection, _TableName, _Sql, _Params) ->
+    erlang:nif_error(nif_not_loaded).
+
+execute_many(_Connection, _Sql, _ParamSets) ->
+    erlang:nif_error(nif_not_loaded).
+
+execute_batch(_Connection, _SqlScript) ->
+    erlang:nif_error(nif_not_loaded).
+
+execute_multi(_Connection, _Sql) ->
+    erlang:nif_error(nif_not_loaded).
+
+validate_sql(_Connection, _Sql) ->
+    erlang:nif_error(nif_not_loaded).
+
+describe(_Connection, _Sql) ->
+    erlang:nif_error(nif_not_loaded).
+
+table_info(_Connection, _TableName) ->
+    erlang:nif_error(nif_not_loaded).
+
+list_schemas(_Connection) ->
+    erlang:nif_error(nif_not_loaded).
+
+list_tables(_Connection, _Schema) ->
+    erlang:nif_error(nif_not_loaded).
+
+table_exists(_Connection, _Schema, _Name) ->
+    erlang:nif_error(nif_not_loaded).
+
+describe_database(_Connection) ->
+    erlang:nif_error(nif_not_loaded).
+
+list_settings(_Connection) ->
+    erlang:nif_error(nif_not_loaded).
+
+explain(_Connection, _Sql, _Params) ->
+    erlang:nif_error(nif_not_loaded).
+
+explain_analyze(_Connection, _Sql, _Params) ->
+    erlang:nif_error(nif_not_loaded).
+
+currval(_Connection, _SequenceName) ->
+    erlang:nif_error(nif_not_loaded).
+
+nextval(_Connection, _SequenceName) ->
+    erlang:nif_error(nif_not_loaded).
+
+register_table(_Connection, _Name, _Rows) ->
+    erlang:nif_error(nif_not_loaded).
+
+import_parquet(_Connection, _TableName, _FilePath, _Options) ->
+    erlang:nif_error(nif_not_loaded).
+
+import_csv(_Connection, _TableName, _Path, _Options) ->
+    erlang:nif_error(nif_not_loaded).
+
+import_json(_Connection, _Table, _Path, _Options) ->
+    erlang:nif_error(nif_not_loaded).
+
+copy_from_iodata(_Connection, _TableName, _Format, _Data) ->
+    erlang:nif_error(nif_not_loaded).
+
+export_parquet(_Connection, _Sql, _Params, _OutPath, _Options) ->
+    erlang:nif_error(nif_not_loaded).
+
+export_csv(_Connection, _Sql, _Params, _OutPath, _Options) ->
+    erlang:nif_error(nif_not_loaded).
+
+execute_query_columnar(_Connection, _Sql, _Params) ->
+    erlang:nif_error(nif_not_loaded).
+
+query_arrow_ipc(_Connection, _Sql, _Params) ->
+    erlang:nif_error(nif_not_loaded).
+
+query_arrow_batches(_Connection, _Sql, _Params, _BatchRows) ->
+    erlang:nif_error(nif_not_loaded).
+
+query_hash(_Connection, _Sql, _Params) ->
+    erlang:nif_error(nif_not_loaded).
+
+query_ndjson(_Connection, _Sql, _Params) ->
+    erlang:nif_error(nif_not_loaded).
+
+pool_open(_Path, _Size) ->
+    erlang:nif_error(nif_not_loaded).
+
+pool_execute(_Pool, _Sql, _Params) ->
+    erlang:nif_error(nif_not_loaded).
+
+appender_create(_Connection, _TableName) ->
+    erlang:nif_error(nif_not_loaded).
+
+appender_append_row(_Appender, _Values) ->
+    erlang:nif_error(nif_not_loaded).
+
+appender_flush(_Appender) ->
+    erlang:nif_error(nif_not_loaded).
+
+register_scalar_function(_Connection, _Name, _Pid) ->
+    erlang:nif_error(nif_not_loaded).
+
+scalar_function_reply(_Call, _Result) ->
+    erlang:nif_error(nif_not_loaded).
+
+query_cursor(_Connection, _Sql, _Params) ->
+    erlang:nif_error(nif_not_loaded).
+
+cursor_fetch(_Cursor, _BatchSize) ->
+    erlang:nif_error(nif_not_loaded).
+
+cell_blob_chunk(_Cursor, _Row, _Column, _Offset, _Length) ->
+    erlang:nif_error(nif_not_loaded).
+
+cursor_close(_Cursor) ->
+    erlang:nif_error(nif_not_loaded).
+
+quote_identifier(_Name) ->
+    erlang:nif_error(nif_not_loaded).
+
+self_test() ->
+    erlang:nif_error(nif_not_loaded).
+
 test() ->
     erlang:nif_error(nif_not_loaded).